@@ -2,16 +2,26 @@ use std::{f32::consts::PI, time::Duration};
 
 use bevy::{
     prelude::{
-        info, Camera, Commands, Component, Entity, GlobalTransform, Input, KeyCode, MouseButton,
-        Query, Res, ResMut, Resource, Vec2, With,
+        info, Commands, Component, Entity, Input, KeyCode, Query, Res, ResMut, Resource, Vec2, With,
     },
     reflect::Reflect,
     time::{Time, Timer, TimerMode},
-    window::{PrimaryWindow, Window},
 };
+use bevy_egui::EguiContexts;
 use rand::Rng;
 
-use crate::render::MainCamera2d;
+use crate::flowfield::FlowField;
+use crate::leaders::{Leader, LeaderDrive, LeaderSettings};
+use crate::params::{BoidParamSettings, BoidParams};
+use crate::paths::Path;
+use crate::physics::PhysicsSettings;
+use crate::steering::{
+    get_arrival_force, get_evasion_force, get_flee_force, get_flow_force, get_follow_force,
+    get_path_following_force, get_pursuit_force, get_seek_force, get_wander_force, limit_length,
+    limit_length_min, SteeringVector,
+};
+use crate::targets::{Target, TargetBehavior};
+use crate::wind::Wind;
 
 #[derive(Reflect, Resource)]
 pub struct BoidSettings {
@@ -21,6 +31,7 @@ pub struct BoidSettings {
     pub spawn_max_position: f32,
     pub max_speed: f32,
     pub max_force: f32,
+    /// Distance in px a boid moves per tick for each unit of velocity.
     pub velocity_time_scale: f32,
     pub tick_time: u64,
     pub cohesion_radius: f32,
@@ -28,9 +39,30 @@ pub struct BoidSettings {
     pub separation_radius: f32,
     pub separation_weight: f32,
     pub collision_weight: f32,
+    /// Passes pushing overlapping boids apart after they moved, 0 allows overlaps.
+    pub collision_iterations: u32,
     pub alignment_weight: f32,
     pub cohesion_weight: f32,
     pub seek_weight: f32,
+    pub wander_weight: f32,
+    /// How far ahead of a boid the wander circle is.
+    pub wander_distance: f32,
+    pub wander_radius: f32,
+    /// Largest change of the wander angle per tick, in radians.
+    pub wander_jitter: f32,
+    pub follow_weight: f32,
+    /// How far behind their leader followers stay.
+    pub follow_distance: f32,
+    /// Followers this close to the path ahead of their leader move out of its way.
+    pub leader_sight_radius: f32,
+    pub path_weight: f32,
+    /// How far ahead boids predict their position and aim along a path.
+    pub path_lookahead: f32,
+    pub flow_weight: f32,
+    /// Distance to a target at which arriving boids start slowing down.
+    pub arrival_radius: f32,
+    /// Upper bound for how many ticks ahead pursuing and evading boids predict a moving target.
+    pub max_prediction: f32,
     pub boundary_min_x: f32,
     pub boundary_max_x: f32,
     pub boundary_min_y: f32,
@@ -50,7 +82,7 @@ impl Default for BoidSettings {
             max_speed: 0.2,
             max_force: 0.05,
 
-            velocity_time_scale: 0.04, // 50.5,
+            velocity_time_scale: 12.0,
 
             tick_time: 20,
 
@@ -58,6 +90,7 @@ impl Default for BoidSettings {
             separation_weight: 1.0,
 
             collision_weight: 0.0,
+            collision_iterations: 0,
 
             alignment_radius: 30.0,
             alignment_weight: 0.8,
@@ -66,6 +99,22 @@ impl Default for BoidSettings {
             cohesion_weight: 0.0002,
 
             seek_weight: 0.0003,
+            wander_weight: 0.0,
+            wander_distance: 30.0,
+            wander_radius: 15.0,
+            wander_jitter: 0.3,
+
+            follow_weight: 1.0,
+            follow_distance: 30.0,
+            leader_sight_radius: 20.0,
+
+            path_weight: 1.0,
+            path_lookahead: 25.0,
+
+            flow_weight: 1.0,
+
+            arrival_radius: 100.0,
+            max_prediction: 50.0,
 
             boundary_min_x: -600.0,
             boundary_max_x: 600.0,
@@ -75,11 +124,6 @@ impl Default for BoidSettings {
     }
 }
 
-#[derive(Debug, Default, Resource)]
-pub struct TargetPosition {
-    pub position: Option<Vec2>,
-}
-
 #[derive(Resource)]
 pub struct BoidTimer(Timer);
 
@@ -89,6 +133,29 @@ impl Default for BoidTimer {
     }
 }
 
+impl BoidTimer {
+    pub fn from_tick_time(tick_time: u64) -> Self {
+        Self(Timer::new(
+            Duration::from_millis(tick_time),
+            TimerMode::Repeating,
+        ))
+    }
+
+    pub fn tick(&mut self, delta: Duration) {
+        self.0.tick(delta);
+    }
+
+    /// Whether the simulation ticked during this frame.
+    pub fn finished(&self) -> bool {
+        self.0.finished()
+    }
+
+    /// Fraction of the time until the next tick that has passed, between 0.0 and 1.0.
+    pub fn progress(&self) -> f32 {
+        self.0.percent()
+    }
+}
+
 #[derive(Component)]
 pub struct Boid;
 
@@ -98,21 +165,39 @@ pub struct Position(pub Vec2);
 #[derive(Debug, Clone, Component)]
 pub struct Velocity(pub Vec2);
 
+/// Weighted steering forces that went into the acceleration of the last tick.
+#[derive(Debug, Default, Clone, Component)]
+pub struct SteeringForces {
+    pub separation: Vec2,
+    pub alignment: Vec2,
+    pub cohesion: Vec2,
+    pub collision: Vec2,
+    pub seek: Vec2,
+    pub wander: Vec2,
+    pub follow: Vec2,
+    pub path: Vec2,
+    pub flow: Vec2,
+    pub boundary: Vec2,
+    /// Added after limiting the steering forces.
+    pub wind: Vec2,
+    /// Change of velocity from drag, zero without the physics model.
+    pub drag: Vec2,
+}
+
+/// Point on the wander circle a boid steers towards, as an angle relative to its heading.
 #[derive(Debug, Clone, Component)]
-pub struct ViewRadius(pub f32);
+pub struct Wander(pub f32);
 
 pub fn setup_boids(
     mut commands: Commands,
     settings: Res<BoidSettings>,
+    leader_settings: Res<LeaderSettings>,
+    param_settings: Res<BoidParamSettings>,
     mut boid_timer: ResMut<BoidTimer>,
 ) {
-    boid_timer.0 = Timer::new(
-        Duration::from_millis(settings.tick_time),
-        TimerMode::Repeating,
-    );
+    *boid_timer = BoidTimer::from_tick_time(settings.tick_time);
 
     let mut rng = rand::thread_rng();
-    let view_radius = 5.0;
 
     let mut positions: Vec<Vec2> = Vec::new();
     for _ in 0..settings.spawn_count {
@@ -127,18 +212,28 @@ pub fn setup_boids(
                 let distance = pos.distance(candidate);
                 distance < settings.boid_radius * 2.0
             }) {
+                let params = if param_settings.enabled {
+                    Some(param_settings.sample(&mut rng))
+                } else {
+                    None
+                };
+                let max_speed = params.map_or(settings.max_speed, |params| params.max_speed);
                 let angle = rng.gen_range(0.0..(PI * 2.0));
-                let initial_velocity = Vec2::new(
-                    angle.cos() * settings.max_speed,
-                    angle.sin() * settings.max_speed,
-                );
+                let initial_velocity = Vec2::new(angle.cos() * max_speed, angle.sin() * max_speed);
 
-                commands.spawn((
+                let mut boid = commands.spawn((
                     Boid,
                     Position(candidate),
                     Velocity(initial_velocity),
-                    ViewRadius(view_radius),
+                    SteeringForces::default(),
+                    Wander(rng.gen_range(-PI..PI)),
                 ));
+                if positions.len() < leader_settings.count as usize {
+                    boid.insert(Leader);
+                }
+                if let Some(params) = params {
+                    boid.insert(params);
+                }
                 positions.push(candidate);
                 break;
             }
@@ -147,49 +242,44 @@ pub fn setup_boids(
     info!("spawned {} boids", positions.len());
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn respawn_boids(
     mut commands: Commands,
     boid_timer: ResMut<BoidTimer>,
     boids: Query<Entity, With<Boid>>,
     keys: Res<Input<KeyCode>>,
     settings: Res<BoidSettings>,
+    leader_settings: Res<LeaderSettings>,
+    param_settings: Res<BoidParamSettings>,
+    mut contexts: EguiContexts,
 ) {
-    if keys.just_pressed(KeyCode::Space) {
+    if keys.just_pressed(KeyCode::Space) && !contexts.ctx_mut().wants_keyboard_input() {
         for entity in boids.iter() {
             commands.entity(entity).despawn();
         }
-        setup_boids(commands, settings, boid_timer);
-    }
-}
-
-pub fn update_target_from_mouse_click(
-    buttons: Res<Input<MouseButton>>,
-    windows: Query<&Window, With<PrimaryWindow>>,
-    camera_transform: Query<(&Camera, &GlobalTransform), With<MainCamera2d>>,
-    mut target_position: ResMut<TargetPosition>,
-) {
-    if buttons.just_pressed(MouseButton::Right) {
-        target_position.position = None;
-    } else if buttons.just_pressed(MouseButton::Middle) {
-        let (camera, camera_transform) = camera_transform.single();
-        let window = windows.get_single().unwrap();
-
-        if let Some(world_position) = window
-            .cursor_position()
-            .and_then(|cursor| camera.viewport_to_world(camera_transform, cursor))
-            .map(|ray| ray.origin.truncate())
-        {
-            target_position.position = Some(Vec2::new(world_position.x, world_position.y));
-        }
+        setup_boids(
+            commands,
+            settings,
+            leader_settings,
+            param_settings,
+            boid_timer,
+        );
     }
 }
 
-fn limit_vec2(vector: Vec2, max_length: f32) -> Vec2 {
-    if vector.length() > max_length {
-        vector.normalize() * max_length
-    } else {
-        vector
-    }
+/// Counts the boids within the given distance, excluding the boid itself
+pub fn count_neighbors<V: SteeringVector>(
+    position: V,
+    positions: impl IntoIterator<Item = V>,
+    distance: f32,
+) -> usize {
+    positions
+        .into_iter()
+        .filter(|other_position| {
+            let other_distance = position.distance(*other_position);
+            other_distance > 0.0 && other_distance < distance
+        })
+        .count()
 }
 
 /// Separation, steer away from nearby boids
@@ -203,15 +293,15 @@ fn limit_vec2(vector: Vec2, max_length: f32) -> Vec2 {
 /// max_force: the maximum force that can be applied to this boid
 ///
 /// Returns: separation force vector
-fn get_separation_force(
-    position: Vec2,
-    velocity: Vec2,
-    boids: &[(Vec2, Vec2)],
+pub fn get_separation_force<V: SteeringVector>(
+    position: V,
+    velocity: V,
+    boids: &[(V, V)],
     separation_distance: f32,
     max_speed: f32,
     max_force: f32,
-) -> Vec2 {
-    let mut steer = Vec2::ZERO;
+) -> V {
+    let mut steer = V::ZERO;
     let mut count = 0;
     for (other_position, _) in boids {
         let distance = position.distance(*other_position);
@@ -230,7 +320,7 @@ fn get_separation_force(
         steer = steer.normalize();
         steer *= max_speed;
         steer -= velocity;
-        steer = limit_vec2(steer, max_force);
+        steer = limit_length(steer, max_force);
     }
     steer
 }
@@ -246,15 +336,15 @@ fn get_separation_force(
 /// max_force: the maximum force that can be applied to this boid
 ///
 /// Returns: alignment force vector
-fn get_alignment_force(
-    position: Vec2,
-    velocity: Vec2,
-    boids: &[(Vec2, Vec2)],
+pub fn get_alignment_force<V: SteeringVector>(
+    position: V,
+    velocity: V,
+    boids: &[(V, V)],
     alignment_distance: f32,
     max_speed: f32,
     max_force: f32,
-) -> Vec2 {
-    let mut average_velocity = Vec2::ZERO;
+) -> V {
+    let mut average_velocity = V::ZERO;
     let mut count = 0;
     for (other_position, other_velocity) in boids {
         let distance = position.distance(*other_position);
@@ -268,29 +358,93 @@ fn get_alignment_force(
         average_velocity = average_velocity.normalize();
         average_velocity *= max_speed;
         average_velocity -= velocity;
-        average_velocity = limit_vec2(average_velocity, max_force);
+        average_velocity = limit_length(average_velocity, max_force);
         average_velocity
     } else {
-        Vec2::ZERO
+        V::ZERO
     }
 }
 
-fn get_seek_force(
+/// Target steering, each target is sought, arrived at or pursued depending on its behavior,
+/// targets with negative influence are fled from or evaded instead
+///
+/// Arguments:
+/// position: the current position of this boid
+/// velocity: the current velocity of this boid
+/// targets: all targets
+/// slowing_radius: distance at which arriving boids start slowing down
+/// max_prediction: upper bound for the ticks pursuing boids look ahead
+/// velocity_scale: px per tick a boid moves for each unit of velocity
+/// max_speed: the maximum speed of this boid
+/// max_force: the maximum force that can be applied to this boid
+///
+/// Returns: target force vector, each target scaled by its influence
+#[allow(clippy::too_many_arguments)]
+fn get_target_force(
     position: Vec2,
     velocity: Vec2,
-    target: Vec2,
+    targets: &[Target],
+    slowing_radius: f32,
+    max_prediction: f32,
+    velocity_scale: f32,
     max_speed: f32,
     max_force: f32,
 ) -> Vec2 {
-    let mut desired = target - position;
-    if desired == Vec2::ZERO {
+    if velocity_scale <= 0.0 {
         return Vec2::ZERO;
     }
-    desired = desired.normalize();
-    desired *= max_speed;
-    desired -= velocity;
-    desired = limit_vec2(desired, max_force);
-    desired
+    // targets move in px per tick, predict in the units of the boid velocity
+    let max_prediction = max_prediction * velocity_scale;
+    let mut steer = Vec2::ZERO;
+    for target in targets {
+        let target_velocity = target.velocity / velocity_scale;
+        let influence = target.influence(position);
+        if influence == 0.0 {
+            continue;
+        }
+        let force = match (target.behavior, influence > 0.0) {
+            (TargetBehavior::Seek, true) => {
+                get_seek_force(position, velocity, target.position, max_speed, max_force)
+            }
+            (TargetBehavior::Arrive, true) => get_arrival_force(
+                position,
+                velocity,
+                target.position,
+                slowing_radius,
+                max_speed,
+                max_force,
+            ),
+            (TargetBehavior::Pursue, true) => get_pursuit_force(
+                position,
+                velocity,
+                target.position,
+                target_velocity,
+                max_prediction,
+                max_speed,
+                max_force,
+            ),
+            (TargetBehavior::Seek | TargetBehavior::Arrive, false) => get_flee_force(
+                position,
+                velocity,
+                target.position,
+                target.radius,
+                max_speed,
+                max_force,
+            ),
+            (TargetBehavior::Pursue, false) => get_evasion_force(
+                position,
+                velocity,
+                target.position,
+                target_velocity,
+                max_prediction,
+                target.radius,
+                max_speed,
+                max_force,
+            ),
+        };
+        steer += force * influence.abs();
+    }
+    steer
 }
 
 /// Cohesion, steer towards the average position of nearby boids
@@ -304,15 +458,15 @@ fn get_seek_force(
 /// max_force: the maximum force that can be applied to this boid
 ///
 /// Returns: cohesion force vector
-fn get_cohesion_force(
-    position: Vec2,
-    velocity: Vec2,
-    boids: &[(Vec2, Vec2)],
+pub fn get_cohesion_force<V: SteeringVector>(
+    position: V,
+    velocity: V,
+    boids: &[(V, V)],
     cohesion_distance: f32,
     max_speed: f32,
     max_force: f32,
-) -> Vec2 {
-    let mut average_position = Vec2::ZERO;
+) -> V {
+    let mut average_position = V::ZERO;
     let mut count = 0;
     for (other_position, _) in boids {
         let distance = position.distance(*other_position);
@@ -326,107 +480,303 @@ fn get_cohesion_force(
         if average_position.length() > 0.0 {
             get_seek_force(position, velocity, average_position, max_speed, max_force)
         } else {
-            Vec2::ZERO
+            V::ZERO
         }
     } else {
-        Vec2::ZERO
+        V::ZERO
+    }
+}
+
+/// Weighted flocking forces of a boid, the part of the steering shared by the 2D and
+/// the 3D simulation.
+#[derive(Debug, Clone, Copy)]
+pub struct FlockingForces<V> {
+    pub separation: V,
+    pub alignment: V,
+    pub cohesion: V,
+    pub collision: V,
+}
+
+impl<V: SteeringVector> FlockingForces<V> {
+    pub fn sum(&self) -> V {
+        self.separation + self.alignment + self.cohesion + self.collision
+    }
+}
+
+/// Flocking, separation, alignment and cohesion plus the collision avoidance, each scaled
+/// by its weight
+///
+/// Arguments:
+/// position: the current position of this boid
+/// velocity: the current velocity of this boid
+/// boids: the position and velocity of all boids (including itself)
+/// settings: the weights and the boid radius
+/// params: the radii, maximum speed and maximum force of this boid
+///
+/// Returns: weighted flocking forces
+pub fn get_flocking_forces<V: SteeringVector>(
+    position: V,
+    velocity: V,
+    boids: &[(V, V)],
+    settings: &BoidSettings,
+    params: &BoidParams,
+) -> FlockingForces<V> {
+    let collision_force = get_separation_force(
+        position,
+        velocity,
+        boids,
+        settings.boid_radius,
+        params.max_speed,
+        params.max_force,
+    );
+    let separation_force = get_separation_force(
+        position,
+        velocity,
+        boids,
+        params.separation_radius,
+        params.max_speed,
+        params.max_force,
+    );
+    let alignment_force = get_alignment_force(
+        position,
+        velocity,
+        boids,
+        params.alignment_radius,
+        params.max_speed,
+        params.max_force,
+    );
+    let cohesion_force = get_cohesion_force(
+        position,
+        velocity,
+        boids,
+        params.cohesion_radius,
+        params.max_speed,
+        params.max_force,
+    );
+    FlockingForces {
+        separation: separation_force * settings.separation_weight,
+        alignment: alignment_force * settings.alignment_weight,
+        cohesion: cohesion_force * settings.cohesion_weight,
+        collision: collision_force * settings.collision_weight,
     }
 }
 
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
 pub fn update(
     time: Res<Time>,
     mut timer: ResMut<BoidTimer>,
     settings: Res<BoidSettings>,
-    target: Res<TargetPosition>,
-    mut query: Query<(&Position, &mut Velocity), With<Boid>>,
+    leader_settings: Res<LeaderSettings>,
+    targets: Query<&Target>,
+    paths: Query<&Path>,
+    flow_field: Res<FlowField>,
+    wind: Res<Wind>,
+    physics: Res<PhysicsSettings>,
+    mut query: Query<
+        (
+            &Position,
+            &mut Velocity,
+            &mut SteeringForces,
+            &mut Wander,
+            Option<&Leader>,
+            Option<&BoidParams>,
+        ),
+        With<Boid>,
+    >,
 ) {
-    timer.0.tick(time.delta());
-    if !timer.0.finished() {
+    timer.tick(time.delta());
+    if !timer.finished() {
         return;
     }
 
     let boids: Vec<(Vec2, Vec2)> = query
         .iter()
-        .map(|(position, velocity)| (position.0, velocity.0))
+        .map(|(position, velocity, _, _, _, _)| (position.0, velocity.0))
         .collect();
+    let leaders: Vec<(Vec2, Vec2)> = query
+        .iter()
+        .filter(|(_, _, _, _, leader, _)| leader.is_some())
+        .map(|(position, velocity, _, _, _, _)| (position.0, velocity.0))
+        .collect();
+    let targets: Vec<Target> = targets.iter().copied().collect();
+    let paths: Vec<(Vec<Vec2>, f32)> = paths
+        .iter()
+        .map(|path| (path.points(), path.width / 2.0))
+        .collect();
+    let shared_params = BoidParams::from_settings(&settings, &physics);
+    let mut rng = rand::thread_rng();
 
-    for (position, mut velocity) in query.iter_mut() {
-        let collision_force = get_separation_force(
-            position.0,
-            velocity.0,
-            &boids,
-            settings.boid_radius,
-            settings.max_speed,
-            settings.max_force,
-        );
-        let separation_force = get_separation_force(
-            position.0,
-            velocity.0,
-            &boids,
-            settings.separation_radius,
-            settings.max_speed,
-            settings.max_force,
-        );
-        let alignment_force = get_alignment_force(
-            position.0,
-            velocity.0,
-            &boids,
-            settings.alignment_radius,
-            settings.max_speed,
-            settings.max_force,
-        );
-        let cohesion_force = get_cohesion_force(
-            position.0,
-            velocity.0,
-            &boids,
-            settings.cohesion_radius,
-            settings.max_speed,
-            settings.max_force,
-        );
-
-        let mut acceleration = separation_force * settings.separation_weight;
-        acceleration += alignment_force * settings.alignment_weight;
-        acceleration += cohesion_force * settings.cohesion_weight;
-        acceleration += collision_force * settings.collision_weight;
-
-        if let Some(target_position) = target.position {
-            let force = get_seek_force(
+    for (position, mut velocity, mut forces, mut wander, leader, params) in query.iter_mut() {
+        let is_leader = leader.is_some();
+        let params = params.unwrap_or(&shared_params);
+        let flocking = get_flocking_forces(position.0, velocity.0, &boids, &settings, params);
+
+        // leaders lead instead of flocking, they only avoid collisions
+        let flocking_weight = if is_leader { 0.0 } else { 1.0 };
+        forces.separation = flocking.separation * flocking_weight;
+        forces.alignment = flocking.alignment * flocking_weight;
+        forces.cohesion = flocking.cohesion * flocking_weight;
+        forces.collision = flocking.collision;
+
+        // leaders only head for targets with the targets drive, not while wandering
+        // or following the mouse
+        let seeks_targets = if is_leader {
+            leader_settings.drive == LeaderDrive::Targets
+        } else {
+            leaders.is_empty() || leader_settings.followers_seek_targets
+        };
+        forces.seek = if seeks_targets {
+            get_target_force(
                 position.0,
                 velocity.0,
-                target_position,
-                settings.max_speed,
-                settings.max_force,
-            );
-            acceleration += force * settings.seek_weight;
+                &targets,
+                settings.arrival_radius,
+                settings.max_prediction,
+                settings.velocity_time_scale,
+                params.max_speed,
+                params.max_force,
+            ) * settings.seek_weight
+        } else {
+            Vec2::ZERO
+        };
+        let leader_cursor = leader_settings
+            .cursor
+            .filter(|_| is_leader && leader_settings.drive == LeaderDrive::Mouse);
+        if let Some(cursor) = leader_cursor {
+            forces.seek += get_seek_force(
+                position.0,
+                velocity.0,
+                cursor,
+                params.max_speed,
+                params.max_force,
+            ) * leader_settings.weight;
         }
 
+        if settings.wander_jitter > 0.0 {
+            wander.0 += rng.gen_range(-settings.wander_jitter..=settings.wander_jitter);
+            wander.0 = (wander.0 + PI).rem_euclid(PI * 2.0) - PI;
+        }
+        forces.wander = get_wander_force(
+            position.0,
+            velocity.0,
+            wander.0,
+            settings.wander_distance,
+            settings.wander_radius,
+            params.max_speed,
+            params.max_force,
+        ) * if is_leader && leader_settings.drive == LeaderDrive::Wander {
+            leader_settings.weight
+        } else {
+            settings.wander_weight
+        };
+
+        // followers trail the closest leader
+        let closest_leader = leaders.iter().min_by(|a, b| {
+            a.0.distance(position.0)
+                .total_cmp(&b.0.distance(position.0))
+        });
+        forces.follow = match closest_leader {
+            Some((leader_position, leader_velocity)) if !is_leader => {
+                get_follow_force(
+                    position.0,
+                    velocity.0,
+                    *leader_position,
+                    *leader_velocity,
+                    settings.follow_distance,
+                    settings.leader_sight_radius,
+                    settings.arrival_radius,
+                    params.max_speed,
+                    params.max_force,
+                ) * settings.follow_weight
+            }
+            _ => Vec2::ZERO,
+        };
+
+        // follow the closest path
+        forces.path = paths
+            .iter()
+            .filter_map(|(points, radius)| {
+                get_path_following_force(
+                    position.0,
+                    velocity.0,
+                    points,
+                    *radius,
+                    settings.path_lookahead,
+                    params.max_speed,
+                    params.max_force,
+                )
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map_or(Vec2::ZERO, |(force, _)| force * settings.path_weight);
+
+        forces.flow = get_flow_force(
+            velocity.0,
+            flow_field.sample(position.0),
+            params.max_speed,
+            params.max_force,
+        ) * settings.flow_weight;
+
+        let mut acceleration = forces.separation
+            + forces.alignment
+            + forces.cohesion
+            + forces.collision
+            + forces.seek
+            + forces.wander
+            + forces.follow
+            + forces.path
+            + forces.flow;
+        let steering = acceleration;
+
         // Boundary avoidance
         if position.0.x < settings.boundary_min_x {
-            acceleration.x = settings.max_force;
+            acceleration.x = params.max_force;
         }
         if position.0.x > settings.boundary_max_x {
-            acceleration.x = -settings.max_force;
+            acceleration.x = -params.max_force;
         }
         if position.0.y < settings.boundary_min_y {
-            acceleration.y = settings.max_force;
+            acceleration.y = params.max_force;
         }
         if position.0.y > settings.boundary_max_y {
-            acceleration.y = -settings.max_force;
+            acceleration.y = -params.max_force;
         }
+        forces.boundary = acceleration - steering;
+
+        acceleration = limit_length(acceleration, params.max_force);
 
-        acceleration = limit_vec2(acceleration, settings.max_force);
+        // wind is an external force, boids can't steer harder to make up for it
+        forces.wind = wind.force(position.0);
+        acceleration += forces.wind;
 
-        velocity.0 += acceleration;
-        velocity.0 = limit_vec2(velocity.0, settings.max_speed);
+        if physics.enabled {
+            let mass = params.mass.max(0.01);
+            forces.drag = physics.drag(velocity.0, mass);
+            velocity.0 += acceleration / mass + forces.drag;
+            // the max speed wins over a stall speed set above it
+            velocity.0 = limit_length(velocity.0, params.max_speed);
+            velocity.0 = limit_length_min(velocity.0, physics.min_speed.min(params.max_speed));
+        } else {
+            forces.drag = Vec2::ZERO;
+            velocity.0 += acceleration;
+            velocity.0 = limit_length(velocity.0, params.max_speed);
+        }
     }
 }
 
+/// Number of ticks, usually fractional, that passed since the last frame. Moving things
+/// by this every frame keeps them in step with the tick rate.
+pub fn frame_ticks(time: &Time, settings: &BoidSettings) -> f32 {
+    time.delta_seconds() * 1000.0 / settings.tick_time.max(1) as f32
+}
+
+/// Moves the boids every frame by the ticks since the last frame.
 pub fn apply_boid_velocity(
     time: Res<Time>,
     settings: Res<BoidSettings>,
     mut boids: Query<(&mut Position, &Velocity), With<Boid>>,
 ) {
+    let scale = frame_ticks(&time, &settings) * settings.velocity_time_scale;
     for (mut position, velocity) in boids.iter_mut() {
-        position.0 += velocity.0 * (time.elapsed_seconds() * settings.velocity_time_scale);
+        position.0 += velocity.0 * scale;
     }
 }
@@ -0,0 +1,213 @@
+use bevy::{
+    prelude::{info, Commands, Component, Query, Res, ResMut, Resource, Vec3, With},
+    time::Time,
+};
+use rand::Rng;
+
+use crate::boids::{frame_ticks, get_flocking_forces, BoidSettings, BoidTimer};
+use crate::params::BoidParams;
+use crate::physics::PhysicsSettings;
+use crate::steering::{get_seek_force, limit_length};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoundsShape {
+    Box,
+    Sphere,
+}
+
+impl BoundsShape {
+    pub const ALL: [BoundsShape; 2] = [BoundsShape::Box, BoundsShape::Sphere];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BoundsShape::Box => "Box",
+            BoundsShape::Sphere => "Sphere",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "box" => Some(BoundsShape::Box),
+            "sphere" => Some(BoundsShape::Sphere),
+            _ => None,
+        }
+    }
+}
+
+/// Settings only the 3D mode has, the flocking settings are shared with 2D.
+#[derive(Debug, Resource)]
+pub struct Boids3dSettings {
+    pub bounds: BoundsShape,
+    /// Half the edge length of the box, or the radius of the sphere.
+    pub bounds_size: f32,
+    /// Point the boids seek with the seek weight.
+    pub seek_target: Vec3,
+}
+
+impl Default for Boids3dSettings {
+    fn default() -> Self {
+        Self {
+            bounds: BoundsShape::Box,
+            bounds_size: 400.0,
+            seek_target: Vec3::ZERO,
+        }
+    }
+}
+
+impl Boids3dSettings {
+    pub fn contains(&self, position: Vec3) -> bool {
+        match self.bounds {
+            BoundsShape::Box => position.abs().max_element() <= self.bounds_size,
+            BoundsShape::Sphere => position.length() <= self.bounds_size,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct Boid3d;
+
+#[derive(Debug, Clone, Component)]
+pub struct Position3d(pub Vec3);
+
+#[derive(Debug, Clone, Component)]
+pub struct Velocity3d(pub Vec3);
+
+/// Random direction, evenly spread over the sphere.
+fn random_direction(rng: &mut impl Rng) -> Vec3 {
+    loop {
+        let candidate = Vec3::new(
+            rng.gen_range(-1.0..1.0),
+            rng.gen_range(-1.0..1.0),
+            rng.gen_range(-1.0..1.0),
+        );
+        if let Some(direction) = candidate
+            .try_normalize()
+            .filter(|_| candidate.length() <= 1.0)
+        {
+            return direction;
+        }
+    }
+}
+
+pub fn setup_boids_3d(
+    mut commands: Commands,
+    settings: Res<BoidSettings>,
+    settings_3d: Res<Boids3dSettings>,
+    mut boid_timer: ResMut<BoidTimer>,
+) {
+    *boid_timer = BoidTimer::from_tick_time(settings.tick_time);
+
+    let mut rng = rand::thread_rng();
+    // spawn inside of the bounds, most candidates would miss a small sphere otherwise
+    let spawn_range = settings.spawn_min_position.max(-settings_3d.bounds_size)
+        ..settings.spawn_max_position.min(settings_3d.bounds_size);
+
+    let mut positions: Vec<Vec3> = Vec::new();
+    for _ in 0..settings.spawn_count {
+        for _ in 0..10 {
+            let candidate = Vec3::new(
+                rng.gen_range(spawn_range.clone()),
+                rng.gen_range(spawn_range.clone()),
+                rng.gen_range(spawn_range.clone()),
+            );
+            if !settings_3d.contains(candidate) {
+                continue;
+            }
+
+            // any overlapping?
+            if !positions
+                .iter()
+                .any(|pos| pos.distance(candidate) < settings.boid_radius * 2.0)
+            {
+                commands.spawn((
+                    Boid3d,
+                    Position3d(candidate),
+                    Velocity3d(random_direction(&mut rng) * settings.max_speed),
+                ));
+                positions.push(candidate);
+                break;
+            }
+        }
+    }
+    info!("spawned {} boids", positions.len());
+}
+
+/// Turns boids outside of the bounds back with the maximum force. The box overrides the
+/// acceleration per axis like the 2D boundary, the sphere towards its center.
+fn apply_bounds(
+    position: Vec3,
+    acceleration: Vec3,
+    settings_3d: &Boids3dSettings,
+    max_force: f32,
+) -> Vec3 {
+    let size = settings_3d.bounds_size;
+    match settings_3d.bounds {
+        BoundsShape::Box => {
+            let mut acceleration = acceleration;
+            for axis in 0..3 {
+                if position[axis] < -size {
+                    acceleration[axis] = max_force;
+                }
+                if position[axis] > size {
+                    acceleration[axis] = -max_force;
+                }
+            }
+            acceleration
+        }
+        BoundsShape::Sphere if position.length() > size => -position.normalize() * max_force,
+        BoundsShape::Sphere => acceleration,
+    }
+}
+
+/// Flocking in 3D, with the separation, alignment, cohesion and seek forces of the
+/// 2D simulation. Targets, paths, leaders, wind and the flow field are 2D only.
+pub fn update_3d(
+    time: Res<Time>,
+    mut timer: ResMut<BoidTimer>,
+    settings: Res<BoidSettings>,
+    settings_3d: Res<Boids3dSettings>,
+    mut query: Query<(&Position3d, &mut Velocity3d), With<Boid3d>>,
+) {
+    timer.tick(time.delta());
+    if !timer.finished() {
+        return;
+    }
+
+    let boids: Vec<(Vec3, Vec3)> = query
+        .iter()
+        .map(|(position, velocity)| (position.0, velocity.0))
+        .collect();
+
+    // the physics model is 2D only, the mass is unused
+    let params = BoidParams::from_settings(&settings, &PhysicsSettings::default());
+
+    for (position, mut velocity) in query.iter_mut() {
+        let flocking = get_flocking_forces(position.0, velocity.0, &boids, &settings, &params);
+        let seek_force = get_seek_force(
+            position.0,
+            velocity.0,
+            settings_3d.seek_target,
+            settings.max_speed,
+            settings.max_force,
+        );
+
+        let mut acceleration = flocking.sum() + seek_force * settings.seek_weight;
+        acceleration = apply_bounds(position.0, acceleration, &settings_3d, settings.max_force);
+        acceleration = limit_length(acceleration, settings.max_force);
+
+        velocity.0 += acceleration;
+        velocity.0 = limit_length(velocity.0, settings.max_speed);
+    }
+}
+
+/// Moves the boids every frame by the ticks since the last frame, like `apply_boid_velocity`.
+pub fn apply_boid_velocity_3d(
+    time: Res<Time>,
+    settings: Res<BoidSettings>,
+    mut boids: Query<(&mut Position3d, &Velocity3d), With<Boid3d>>,
+) {
+    let scale = frame_ticks(&time, &settings) * settings.velocity_time_scale;
+    for (mut position, velocity) in boids.iter_mut() {
+        position.0 += velocity.0 * scale;
+    }
+}
@@ -0,0 +1,202 @@
+use bevy::{
+    input::mouse::{MouseScrollUnit, MouseWheel},
+    prelude::{
+        Camera, EventReader, GlobalTransform, Input, KeyCode, MouseButton, OrthographicProjection,
+        Query, Res, ResMut, Resource, Transform, Vec2, With, Without,
+    },
+    window::{PrimaryWindow, Window},
+};
+use bevy_egui::EguiContexts;
+
+use crate::boids::{Boid, BoidSettings, Position};
+use crate::picking::SelectedBoid;
+use crate::render::{get_cursor_world_position, MainCamera2d};
+
+/// Cursor movement in pixels after which a left button press pans instead of picks.
+const DRAG_THRESHOLD: f32 = 4.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CameraFollow {
+    /// The camera stays where it is.
+    Off,
+    /// The camera is centered on the average position of all boids.
+    Centroid,
+    /// The camera is centered on the selected boid.
+    SelectedBoid,
+}
+
+#[derive(Resource)]
+pub struct CameraSettings {
+    pub follow: CameraFollow,
+    /// Fits the camera to the arena on the next frame, set by the UI.
+    pub fit_arena: bool,
+    pub min_zoom: f32,
+    pub max_zoom: f32,
+    /// Scale change per line scrolled with the mouse wheel.
+    pub zoom_speed: f32,
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        Self {
+            follow: CameraFollow::Off,
+            fit_arena: false,
+            min_zoom: 0.05,
+            max_zoom: 10.0,
+            zoom_speed: 0.1,
+        }
+    }
+}
+
+/// State of a left mouse button drag, shared with picking so a pan does not select a boid.
+#[derive(Debug, Default, Resource)]
+pub struct MouseDrag {
+    /// Cursor position when the button was pressed outside of egui.
+    pub press_cursor: Option<Vec2>,
+    pub last_cursor: Option<Vec2>,
+    /// Whether the cursor moved far enough to count as a drag.
+    pub moved: bool,
+}
+
+/// Camera scale and position that show the whole arena in the window.
+fn get_arena_fit(settings: &BoidSettings, window: &Window) -> (f32, Vec2) {
+    let min = Vec2::new(settings.boundary_min_x, settings.boundary_min_y);
+    let max = Vec2::new(settings.boundary_max_x, settings.boundary_max_y);
+    let size = (max - min) * 1.05;
+    let scale = (size.x / window.width()).max(size.y / window.height());
+    (scale, (min + max) / 2.0)
+}
+
+fn fit_camera_to_arena(
+    settings: &BoidSettings,
+    window: &Window,
+    camera_settings: &mut CameraSettings,
+    transform: &mut Transform,
+    projection: &mut OrthographicProjection,
+) {
+    let (scale, center) = get_arena_fit(settings, window);
+    projection.scale = scale.clamp(camera_settings.min_zoom, camera_settings.max_zoom);
+    transform.translation.x = center.x;
+    transform.translation.y = center.y;
+    camera_settings.follow = CameraFollow::Off;
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn update_camera_from_input(
+    buttons: Res<Input<MouseButton>>,
+    keys: Res<Input<KeyCode>>,
+    mut wheel: EventReader<MouseWheel>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut contexts: EguiContexts,
+    settings: Res<BoidSettings>,
+    mut camera_settings: ResMut<CameraSettings>,
+    mut drag: ResMut<MouseDrag>,
+    mut camera: Query<
+        (
+            &Camera,
+            &GlobalTransform,
+            &mut Transform,
+            &mut OrthographicProjection,
+        ),
+        With<MainCamera2d>,
+    >,
+) {
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    let Ok((camera, camera_transform, mut transform, mut projection)) = camera.get_single_mut()
+    else {
+        return;
+    };
+    let egui_wants_pointer = contexts.ctx_mut().wants_pointer_input();
+    let egui_wants_keyboard = contexts.ctx_mut().wants_keyboard_input();
+    let cursor = window.cursor_position();
+
+    if (keys.just_pressed(KeyCode::F) && !egui_wants_keyboard) || camera_settings.fit_arena {
+        camera_settings.fit_arena = false;
+        fit_camera_to_arena(
+            &settings,
+            window,
+            &mut camera_settings,
+            &mut transform,
+            &mut projection,
+        );
+    }
+
+    // pan with the left mouse button
+    if buttons.just_pressed(MouseButton::Left) {
+        *drag = MouseDrag::default();
+        if !egui_wants_pointer {
+            drag.press_cursor = cursor;
+            drag.last_cursor = cursor;
+        }
+    } else if buttons.pressed(MouseButton::Left) {
+        if let (Some(press), Some(last), Some(cursor)) =
+            (drag.press_cursor, drag.last_cursor, cursor)
+        {
+            if !drag.moved && press.distance(cursor) > DRAG_THRESHOLD {
+                drag.moved = true;
+                camera_settings.follow = CameraFollow::Off;
+            }
+            if drag.moved {
+                // window coordinates point down, world coordinates up
+                let delta = cursor - last;
+                transform.translation.x -= delta.x * projection.scale;
+                transform.translation.y += delta.y * projection.scale;
+            }
+            drag.last_cursor = Some(cursor);
+        }
+    } else if !buttons.just_released(MouseButton::Left) {
+        // keep the state for one frame after release so picking can tell clicks from drags
+        *drag = MouseDrag::default();
+    }
+
+    // zoom with the mouse wheel, keeping the point under the cursor in place
+    let scrolled: f32 = wheel
+        .iter()
+        .map(|event| match event.unit {
+            MouseScrollUnit::Line => event.y,
+            MouseScrollUnit::Pixel => event.y / 20.0,
+        })
+        .sum();
+    if scrolled != 0.0 && !egui_wants_pointer {
+        let old_scale = projection.scale;
+        let new_scale = (old_scale * (1.0 - camera_settings.zoom_speed).powf(scrolled))
+            .clamp(camera_settings.min_zoom, camera_settings.max_zoom);
+        projection.scale = new_scale;
+
+        if let Some(anchor) = get_cursor_world_position(window, camera, camera_transform) {
+            let position = transform.translation.truncate();
+            let position = anchor + (position - anchor) * (new_scale / old_scale);
+            transform.translation.x = position.x;
+            transform.translation.y = position.y;
+        }
+    }
+}
+
+pub fn follow_camera(
+    camera_settings: Res<CameraSettings>,
+    selected: Res<SelectedBoid>,
+    boids: Query<&Position, With<Boid>>,
+    mut camera: Query<&mut Transform, (With<MainCamera2d>, Without<Boid>)>,
+) {
+    let target = match camera_settings.follow {
+        CameraFollow::Off => None,
+        CameraFollow::Centroid => {
+            let count = boids.iter().len();
+            if count > 0 {
+                Some(boids.iter().map(|position| position.0).sum::<Vec2>() / count as f32)
+            } else {
+                None
+            }
+        }
+        CameraFollow::SelectedBoid => selected
+            .0
+            .and_then(|entity| boids.get(entity).ok())
+            .map(|position| position.0),
+    };
+    if let (Some(target), Ok(mut transform)) = (target, camera.get_single_mut()) {
+        transform.translation.x = target.x;
+        transform.translation.y = target.y;
+    }
+}
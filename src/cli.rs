@@ -0,0 +1,520 @@
+use bevy::prelude::Vec2;
+
+use crate::boids::BoidSettings;
+use crate::boids3d::{Boids3dSettings, BoundsShape};
+use crate::export::{ExportFormat, ExportSettings};
+use crate::flowfield::FlowField;
+use crate::leaders::{LeaderDrive, LeaderSettings};
+use crate::params::{BoidParamSettings, Distribution};
+use crate::physics::PhysicsSettings;
+use crate::raster::PngExport;
+use crate::routes::{Arrival, Route, RouteMode};
+use crate::svg::SvgExport;
+use crate::wind::Wind;
+
+pub const USAGE: &str = "usage: bevy-boids [options]
+
+options:
+  --headless               run the simulation without a window
+  --3d                     flock in 3D, only flocking, seek and the metrics export
+                           are supported, other options are rejected
+  --bounds <box|sphere>    shape the 3D boids stay inside of (default: box)
+  --ticks <n>              exit after n simulation ticks
+  --format <csv|json>      export file format (default: csv)
+  --export-metrics <path>  write per-tick flock metrics to path
+  --export-boids <path>    write per-tick boid positions and velocities to path
+  --svg-every <n>          write an SVG frame every n ticks
+  --svg-path <path>        SVG file name, {tick} is replaced (default: frame_{tick}.svg)
+  --png-every <n>          rasterize a PNG frame every n ticks, without a GPU
+  --png-path <path>        PNG file name, {tick} is replaced (default: frame_{tick}.png)
+  --png-width <pixels>     PNG frame width (default: 1024)
+  --route <x,y;x,y;...>    waypoints the flock visits in order, starting right away
+  --route-mode <mode>      once, loop or ping-pong (default: loop)
+  --arrival-fraction <f>   advance when this fraction of boids arrived, instead of
+                           the flock centroid
+  --leaders <n>            number of boids that lead the others
+  --leader-drive <drive>   what steers leaders: targets, wander or mouse
+                           (default: targets)
+  --flow-field <path>      load the flow field boids align to from path
+  --flow-noise <seed>      fill the flow field with curl noise from seed
+  --wind <x,y>             constant wind force added to every boid
+  --turbulence <strength>  strength of the noise turbulence added to the wind
+  --param <name=mean,sd>   draw a per boid parameter from a normal distribution,
+                           repeatable, names: max-speed, max-force,
+                           separation-radius, alignment-radius, cohesion-radius,
+                           mass
+  --collision-iterations <n>
+                           passes pushing overlapping boids apart (default: 0)
+  --drag <linear,quad>     enable the physics model with linear and quadratic drag
+  --min-speed <speed>      enable the physics model with a stall speed
+  -h, --help               print this help";
+
+/// Command line arguments, parsed by hand to keep the dependency list short.
+#[derive(Debug, Default)]
+pub struct Args {
+    pub headless: bool,
+    pub three_d: bool,
+    pub bounds: Option<BoundsShape>,
+    pub ticks: Option<u64>,
+    pub format: Option<ExportFormat>,
+    pub export_metrics: Option<String>,
+    pub export_boids: Option<String>,
+    pub svg_every: Option<u64>,
+    pub svg_path: Option<String>,
+    pub png_every: Option<u64>,
+    pub png_path: Option<String>,
+    pub png_width: Option<u32>,
+    pub route: Option<Vec<Vec2>>,
+    pub route_mode: Option<RouteMode>,
+    pub arrival_fraction: Option<f32>,
+    pub leaders: Option<u32>,
+    pub leader_drive: Option<LeaderDrive>,
+    pub flow_field: Option<String>,
+    pub flow_noise: Option<u32>,
+    pub wind: Option<Vec2>,
+    pub turbulence: Option<f32>,
+    pub params: Vec<(String, Distribution)>,
+    pub collision_iterations: Option<u32>,
+    pub drag: Option<(f32, f32)>,
+    pub min_speed: Option<f32>,
+    pub help: bool,
+}
+
+impl Args {
+    pub fn parse(args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut parsed = Args::default();
+        let mut args = args.skip(1);
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| format!("missing value for {}", arg))
+            };
+            match arg.as_str() {
+                "--headless" => parsed.headless = true,
+                "--3d" => parsed.three_d = true,
+                "--bounds" => {
+                    let bounds = value()?;
+                    parsed.bounds = Some(
+                        BoundsShape::parse(&bounds)
+                            .ok_or_else(|| format!("unknown bounds: {}", bounds))?,
+                    );
+                }
+                "--ticks" => {
+                    let ticks = value()?;
+                    parsed.ticks = Some(
+                        ticks
+                            .parse()
+                            .map_err(|_| format!("invalid tick count: {}", ticks))?,
+                    );
+                }
+                "--format" => {
+                    let format = value()?;
+                    parsed.format = Some(
+                        ExportFormat::parse(&format)
+                            .ok_or_else(|| format!("unknown format: {}", format))?,
+                    );
+                }
+                "--export-metrics" => parsed.export_metrics = Some(value()?),
+                "--export-boids" => parsed.export_boids = Some(value()?),
+                "--svg-every" => {
+                    let every = value()?;
+                    parsed.svg_every = Some(
+                        every
+                            .parse()
+                            .map_err(|_| format!("invalid tick count: {}", every))?,
+                    );
+                }
+                "--svg-path" => parsed.svg_path = Some(value()?),
+                "--png-every" => {
+                    let every = value()?;
+                    parsed.png_every = Some(
+                        every
+                            .parse()
+                            .map_err(|_| format!("invalid tick count: {}", every))?,
+                    );
+                }
+                "--png-path" => parsed.png_path = Some(value()?),
+                "--png-width" => {
+                    let width = value()?;
+                    parsed.png_width = Some(
+                        width
+                            .parse()
+                            .map_err(|_| format!("invalid width: {}", width))?,
+                    );
+                }
+                "--route" => {
+                    let route = value()?;
+                    parsed.route = Some(
+                        Route::parse_waypoints(&route)
+                            .ok_or_else(|| format!("invalid waypoints: {}", route))?,
+                    );
+                }
+                "--route-mode" => {
+                    let mode = value()?;
+                    parsed.route_mode = Some(
+                        RouteMode::parse(&mode)
+                            .ok_or_else(|| format!("unknown route mode: {}", mode))?,
+                    );
+                }
+                "--arrival-fraction" => {
+                    let fraction = value()?;
+                    parsed.arrival_fraction = Some(
+                        fraction
+                            .parse()
+                            .map_err(|_| format!("invalid fraction: {}", fraction))?,
+                    );
+                }
+                "--leaders" => {
+                    let leaders = value()?;
+                    parsed.leaders = Some(
+                        leaders
+                            .parse()
+                            .map_err(|_| format!("invalid leader count: {}", leaders))?,
+                    );
+                }
+                "--leader-drive" => {
+                    let drive = value()?;
+                    parsed.leader_drive = Some(
+                        LeaderDrive::parse(&drive)
+                            .ok_or_else(|| format!("unknown leader drive: {}", drive))?,
+                    );
+                }
+                "--flow-field" => parsed.flow_field = Some(value()?),
+                "--flow-noise" => {
+                    let seed = value()?;
+                    parsed.flow_noise = Some(
+                        seed.parse()
+                            .map_err(|_| format!("invalid noise seed: {}", seed))?,
+                    );
+                }
+                "--wind" => {
+                    let wind = value()?;
+                    parsed.wind = Some(
+                        wind.split_once(',')
+                            .and_then(|(x, y)| {
+                                Some(Vec2::new(x.trim().parse().ok()?, y.trim().parse().ok()?))
+                            })
+                            .ok_or_else(|| format!("invalid wind: {}", wind))?,
+                    );
+                }
+                "--turbulence" => {
+                    let strength = value()?;
+                    parsed.turbulence = Some(
+                        strength
+                            .parse()
+                            .map_err(|_| format!("invalid turbulence strength: {}", strength))?,
+                    );
+                }
+                "--param" => {
+                    let param = value()?;
+                    let invalid = || format!("invalid parameter distribution: {}", param);
+                    let (name, distribution) = param.split_once('=').ok_or_else(invalid)?;
+                    if !BoidParamSettings::NAMES.contains(&name) {
+                        return Err(format!("unknown parameter: {}", name));
+                    }
+                    let (mean, std_dev) = distribution.split_once(',').ok_or_else(invalid)?;
+                    let distribution = Distribution::Normal {
+                        mean: mean.trim().parse().map_err(|_| invalid())?,
+                        std_dev: std_dev.trim().parse().map_err(|_| invalid())?,
+                    };
+                    parsed.params.push((name.to_string(), distribution));
+                }
+                "--collision-iterations" => {
+                    let iterations = value()?;
+                    parsed.collision_iterations = Some(
+                        iterations
+                            .parse()
+                            .map_err(|_| format!("invalid iteration count: {}", iterations))?,
+                    );
+                }
+                "--drag" => {
+                    let drag = value()?;
+                    parsed.drag = Some(
+                        drag.split_once(',')
+                            .and_then(|(linear, quadratic)| {
+                                Some((linear.trim().parse().ok()?, quadratic.trim().parse().ok()?))
+                            })
+                            .ok_or_else(|| format!("invalid drag: {}", drag))?,
+                    );
+                }
+                "--min-speed" => {
+                    let speed = value()?;
+                    parsed.min_speed = Some(
+                        speed
+                            .parse()
+                            .map_err(|_| format!("invalid speed: {}", speed))?,
+                    );
+                }
+                "-h" | "--help" => parsed.help = true,
+                _ => return Err(format!("unknown argument: {}", arg)),
+            }
+        }
+        if parsed.three_d {
+            if let Some(option) = parsed.flat_only_option() {
+                return Err(format!("{} is not supported with --3d", option));
+            }
+        }
+        Ok(parsed)
+    }
+
+    /// First given option the 3D flock would silently ignore.
+    fn flat_only_option(&self) -> Option<&'static str> {
+        [
+            (self.export_boids.is_some(), "--export-boids"),
+            (self.svg_every.is_some(), "--svg-every"),
+            (self.svg_path.is_some(), "--svg-path"),
+            (self.png_every.is_some(), "--png-every"),
+            (self.png_path.is_some(), "--png-path"),
+            (self.png_width.is_some(), "--png-width"),
+            (self.route.is_some(), "--route"),
+            (self.route_mode.is_some(), "--route-mode"),
+            (self.arrival_fraction.is_some(), "--arrival-fraction"),
+            (self.leaders.is_some(), "--leaders"),
+            (self.leader_drive.is_some(), "--leader-drive"),
+            (self.flow_field.is_some(), "--flow-field"),
+            (self.flow_noise.is_some(), "--flow-noise"),
+            (self.wind.is_some(), "--wind"),
+            (self.turbulence.is_some(), "--turbulence"),
+            (!self.params.is_empty(), "--param"),
+            (
+                self.collision_iterations.is_some(),
+                "--collision-iterations",
+            ),
+            (self.drag.is_some(), "--drag"),
+            (self.min_speed.is_some(), "--min-speed"),
+        ]
+        .into_iter()
+        .find(|(given, _)| *given)
+        .map(|(_, option)| option)
+    }
+
+    pub fn export_settings(&self) -> ExportSettings {
+        let mut settings = ExportSettings::default();
+        if let Some(format) = self.format {
+            settings.format = format;
+            settings.metrics_path = format!("metrics.{}", format.extension());
+            settings.boids_path = format!("boids.{}", format.extension());
+        }
+        if let Some(path) = &self.export_metrics {
+            settings.enabled = true;
+            settings.metrics_path = path.clone();
+        }
+        if let Some(path) = &self.export_boids {
+            settings.enabled = true;
+            settings.include_boids = true;
+            settings.boids_path = path.clone();
+        }
+        settings
+    }
+
+    pub fn svg_export(&self) -> SvgExport {
+        let mut export = SvgExport::default();
+        if let Some(every) = self.svg_every {
+            export.every = every;
+        }
+        if let Some(path) = &self.svg_path {
+            export.path = path.clone();
+        }
+        export
+    }
+
+    pub fn png_export(&self) -> PngExport {
+        let mut export = PngExport::default();
+        if let Some(every) = self.png_every {
+            export.every = every;
+        }
+        if let Some(path) = &self.png_path {
+            export.path = path.clone();
+        }
+        if let Some(width) = self.png_width {
+            export.width = width;
+        }
+        export
+    }
+
+    pub fn route(&self) -> Route {
+        let mut route = Route::default();
+        if let Some(mode) = self.route_mode {
+            route.mode = mode;
+        }
+        if let Some(fraction) = self.arrival_fraction {
+            route.arrival = Arrival::Fraction;
+            route.arrival_fraction = fraction;
+        }
+        if let Some(waypoints) = &self.route {
+            route.waypoints = waypoints.clone();
+            route.start();
+        }
+        route
+    }
+
+    pub fn leader_settings(&self) -> LeaderSettings {
+        let mut settings = LeaderSettings::default();
+        if let Some(count) = self.leaders {
+            settings.count = count;
+        }
+        if let Some(drive) = self.leader_drive {
+            settings.drive = drive;
+        }
+        settings
+    }
+
+    pub fn wind(&self) -> Wind {
+        let mut wind = Wind::default();
+        if let Some(force) = self.wind {
+            wind.direction = force.y.atan2(force.x);
+            wind.strength = force.length();
+        }
+        if let Some(strength) = self.turbulence {
+            wind.turbulence_strength = strength;
+        }
+        wind
+    }
+
+    pub fn boid_settings(&self) -> BoidSettings {
+        let mut settings = BoidSettings::default();
+        if let Some(iterations) = self.collision_iterations {
+            settings.collision_iterations = iterations;
+        }
+        settings
+    }
+
+    pub fn boids_3d_settings(&self) -> Boids3dSettings {
+        let mut settings = Boids3dSettings::default();
+        if let Some(bounds) = self.bounds {
+            settings.bounds = bounds;
+        }
+        settings
+    }
+
+    pub fn physics_settings(&self) -> PhysicsSettings {
+        let mut physics = PhysicsSettings::default();
+        if let Some((linear, quadratic)) = self.drag {
+            physics.enabled = true;
+            physics.linear_drag = linear;
+            physics.quadratic_drag = quadratic;
+        }
+        if let Some(speed) = self.min_speed {
+            physics.enabled = true;
+            physics.min_speed = speed;
+        }
+        physics
+    }
+
+    /// Per boid parameters are enabled when any of them is given.
+    pub fn boid_param_settings(
+        &self,
+        settings: &BoidSettings,
+        physics: &PhysicsSettings,
+    ) -> BoidParamSettings {
+        let mut param_settings = BoidParamSettings::from_settings(settings, physics);
+        for (name, distribution) in &self.params {
+            if let Some(param) = param_settings.distribution_mut(name) {
+                *param = *distribution;
+                param_settings.enabled = true;
+            }
+        }
+        param_settings
+    }
+
+    /// Flow field covering the arena, empty unless loaded or generated from the arguments.
+    pub fn flow_field(&self, settings: &BoidSettings) -> Result<FlowField, String> {
+        let mut field = match &self.flow_field {
+            Some(path) => FlowField::load(path)?,
+            None => FlowField::from_settings(settings, 40.0),
+        };
+        if let Some(seed) = self.flow_noise {
+            field.fill_curl_noise(seed, 400.0);
+        }
+        Ok(field)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Args, String> {
+        Args::parse(
+            std::iter::once("bevy-boids")
+                .chain(args.iter().copied())
+                .map(String::from),
+        )
+    }
+
+    #[test]
+    fn parses_values() {
+        let args = parse(&[
+            "--headless",
+            "--ticks",
+            "30",
+            "--format",
+            "json",
+            "--wind",
+            "1.5, -2",
+            "--param",
+            "max-speed=0.2,0.05",
+            "--drag",
+            "0.1,0.01",
+        ])
+        .unwrap();
+        assert!(args.headless);
+        assert_eq!(args.ticks, Some(30));
+        assert_eq!(args.format, Some(ExportFormat::Json));
+        assert_eq!(args.wind, Some(Vec2::new(1.5, -2.0)));
+        assert_eq!(
+            args.params,
+            vec![(
+                "max-speed".to_string(),
+                Distribution::Normal {
+                    mean: 0.2,
+                    std_dev: 0.05
+                }
+            )]
+        );
+        assert_eq!(args.drag, Some((0.1, 0.01)));
+    }
+
+    #[test]
+    fn rejects_invalid_values() {
+        assert_eq!(
+            parse(&["--ticks", "ten"]).unwrap_err(),
+            "invalid tick count: ten"
+        );
+        assert_eq!(
+            parse(&["--format", "xml"]).unwrap_err(),
+            "unknown format: xml"
+        );
+        assert_eq!(
+            parse(&["--param", "speed=1,0"]).unwrap_err(),
+            "unknown parameter: speed"
+        );
+        assert_eq!(parse(&["--wind", "1"]).unwrap_err(), "invalid wind: 1");
+    }
+
+    #[test]
+    fn rejects_missing_values() {
+        assert_eq!(
+            parse(&["--ticks"]).unwrap_err(),
+            "missing value for --ticks"
+        );
+        assert_eq!(
+            parse(&["--headless", "--export-metrics"]).unwrap_err(),
+            "missing value for --export-metrics"
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_flags() {
+        assert_eq!(parse(&["--fast"]).unwrap_err(), "unknown argument: --fast");
+    }
+
+    #[test]
+    fn rejects_flat_only_options_in_3d() {
+        assert_eq!(
+            parse(&["--3d", "--export-boids", "boids.csv"]).unwrap_err(),
+            "--export-boids is not supported with --3d"
+        );
+        assert!(parse(&["--3d", "--bounds", "sphere", "--export-metrics", "m.csv"]).is_ok());
+    }
+}
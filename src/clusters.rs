@@ -0,0 +1,194 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::prelude::{Commands, Component, Entity, Query, Res, ResMut, Resource, Vec2, With};
+
+use crate::boids::{count_neighbors, Boid, BoidSettings, BoidTimer, Position};
+
+/// Index of the cluster a boid belonged to in the last tick, 0 is the largest cluster.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct ClusterId(pub usize);
+
+/// Identity of a flock that persists across ticks, unlike the cluster index.
+///
+/// A cluster keeps the flock id most of its boids had in the previous tick,
+/// when a flock splits the smaller parts get new ids.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct FlockId(pub usize);
+
+/// Local crowding around a boid, updated every simulation tick.
+#[derive(Debug, Default, Clone, Copy, Component)]
+pub struct Neighborhood {
+    /// Number of boids within the alignment radius.
+    pub neighbors: usize,
+    /// Boids within the cohesion radius per 10000 square units.
+    pub density: f32,
+}
+
+/// Connected components of the neighbor graph, updated every simulation tick.
+#[derive(Debug, Default, Resource)]
+pub struct Clusters {
+    pub count: usize,
+    /// Number of boids per cluster, sorted from largest to smallest.
+    pub sizes: Vec<usize>,
+    /// Flock id of each cluster.
+    pub flock_ids: Vec<usize>,
+    next_flock_id: usize,
+}
+
+fn find(parents: &mut [usize], mut index: usize) -> usize {
+    while parents[index] != index {
+        parents[index] = parents[parents[index]];
+        index = parents[index];
+    }
+    index
+}
+
+/// Splits boids into connected components, two boids are connected if they
+/// are closer than the neighbor distance.
+///
+/// Arguments:
+/// positions: the position of all boids
+/// neighbor_distance: how close boids have to be to be connected
+///
+/// Returns: cluster index for each boid and the size of each cluster,
+/// clusters are ordered from largest to smallest
+pub fn find_clusters(positions: &[Vec2], neighbor_distance: f32) -> (Vec<usize>, Vec<usize>) {
+    let mut parents: Vec<usize> = (0..positions.len()).collect();
+    for (i, position) in positions.iter().enumerate() {
+        for (j, other_position) in positions.iter().enumerate().skip(i + 1) {
+            if position.distance(*other_position) < neighbor_distance {
+                let root_i = find(&mut parents, i);
+                let root_j = find(&mut parents, j);
+                if root_i != root_j {
+                    parents[root_j] = root_i;
+                }
+            }
+        }
+    }
+
+    let roots: Vec<usize> = (0..positions.len())
+        .map(|index| find(&mut parents, index))
+        .collect();
+
+    let mut root_sizes = vec![0; positions.len()];
+    for root in roots.iter() {
+        root_sizes[*root] += 1;
+    }
+    let mut order: Vec<usize> = (0..positions.len())
+        .filter(|index| root_sizes[*index] > 0)
+        .collect();
+    order.sort_by(|a, b| root_sizes[*b].cmp(&root_sizes[*a]).then(a.cmp(b)));
+
+    let mut labels = vec![0; positions.len()];
+    for (label, root) in order.iter().enumerate() {
+        labels[*root] = label;
+    }
+    let sizes = order.iter().map(|root| root_sizes[*root]).collect();
+    (roots.iter().map(|root| labels[*root]).collect(), sizes)
+}
+
+/// Carries flock ids over from the previous tick.
+///
+/// Arguments:
+/// labels: cluster index of each boid, clusters ordered from largest to smallest
+/// previous: flock id of each boid in the previous tick, if it had one
+/// cluster_count: number of clusters
+/// next_flock_id: the next unused flock id
+///
+/// Returns: flock id for each cluster
+fn assign_flock_ids(
+    labels: &[usize],
+    previous: &[Option<usize>],
+    cluster_count: usize,
+    next_flock_id: &mut usize,
+) -> Vec<usize> {
+    let mut votes: Vec<HashMap<usize, usize>> = vec![HashMap::new(); cluster_count];
+    for (label, flock_id) in labels.iter().zip(previous) {
+        if let Some(flock_id) = flock_id {
+            *votes[*label].entry(*flock_id).or_default() += 1;
+        }
+    }
+
+    // larger clusters pick first, so the larger part of a split keeps the id
+    let mut taken = HashSet::new();
+    votes
+        .iter()
+        .map(|votes| {
+            let majority = votes
+                .iter()
+                .max_by(|a, b| a.1.cmp(b.1).then(b.0.cmp(a.0)))
+                .map(|(flock_id, _)| *flock_id)
+                .filter(|flock_id| !taken.contains(flock_id));
+            let flock_id = majority.unwrap_or_else(|| {
+                *next_flock_id += 1;
+                *next_flock_id - 1
+            });
+            taken.insert(flock_id);
+            flock_id
+        })
+        .collect()
+}
+
+#[allow(clippy::type_complexity)]
+pub fn update_clusters(
+    mut commands: Commands,
+    timer: Res<BoidTimer>,
+    settings: Res<BoidSettings>,
+    mut clusters: ResMut<Clusters>,
+    mut query: Query<
+        (
+            Entity,
+            &Position,
+            Option<(&mut ClusterId, &mut FlockId, &mut Neighborhood)>,
+        ),
+        With<Boid>,
+    >,
+) {
+    if !timer.finished() {
+        return;
+    }
+
+    let positions: Vec<Vec2> = query.iter().map(|(_, position, _)| position.0).collect();
+    let previous: Vec<Option<usize>> = query
+        .iter()
+        .map(|(_, _, components)| components.map(|(_, flock_id, _)| flock_id.0))
+        .collect();
+
+    let (labels, sizes) = find_clusters(&positions, settings.alignment_radius);
+    let flock_ids = assign_flock_ids(&labels, &previous, sizes.len(), &mut clusters.next_flock_id);
+
+    let density_area = std::f32::consts::PI * settings.cohesion_radius.powi(2) / 10000.0;
+    for ((entity, position, components), label) in query.iter_mut().zip(labels) {
+        let neighborhood = Neighborhood {
+            neighbors: count_neighbors(
+                position.0,
+                positions.iter().copied(),
+                settings.alignment_radius,
+            ),
+            density: count_neighbors(
+                position.0,
+                positions.iter().copied(),
+                settings.cohesion_radius,
+            ) as f32
+                / density_area,
+        };
+        match components {
+            Some((mut cluster_id, mut flock_id, mut current)) => {
+                cluster_id.0 = label;
+                flock_id.0 = flock_ids[label];
+                *current = neighborhood;
+            }
+            None => {
+                commands.entity(entity).insert((
+                    ClusterId(label),
+                    FlockId(flock_ids[label]),
+                    neighborhood,
+                ));
+            }
+        }
+    }
+
+    clusters.count = sizes.len();
+    clusters.sizes = sizes;
+    clusters.flock_ids = flock_ids;
+}
@@ -0,0 +1,144 @@
+use std::collections::HashSet;
+
+use bevy::prelude::{Query, Res, ResMut, Resource, Vec2, With};
+
+use crate::boids::{Boid, BoidSettings, BoidTimer, Position};
+
+/// Overlaps resolved in the current simulation tick, moved to `FlockMetrics` every tick.
+#[derive(Debug, Default, Resource)]
+pub struct Contacts(pub usize);
+
+/// Pushes overlapping boids apart until they are at least two boid radii apart,
+/// both move by half of the overlap.
+///
+/// Arguments:
+/// positions: the positions of all boids, moved in place
+/// boid_radius: the radius of a boid
+/// iterations: how often to repeat the pass, a push can create new overlaps
+///
+/// Returns: number of overlapping pairs, a pair pushed in several iterations counts once
+fn resolve_overlaps(positions: &mut [Vec2], boid_radius: f32, iterations: u32) -> usize {
+    let min_distance = boid_radius * 2.0;
+    let mut contacts: HashSet<(usize, usize)> = HashSet::new();
+    for _ in 0..iterations {
+        let mut resolved = 0;
+        for i in 0..positions.len() {
+            for j in (i + 1)..positions.len() {
+                let offset = positions[i] - positions[j];
+                let distance = offset.length();
+                if distance >= min_distance {
+                    continue;
+                }
+                // boids on the same spot are pushed apart sideways
+                let normal = offset.try_normalize().unwrap_or(Vec2::X);
+                let push = normal * (min_distance - distance) / 2.0;
+                positions[i] += push;
+                positions[j] -= push;
+                contacts.insert((i, j));
+                resolved += 1;
+            }
+        }
+        if resolved == 0 {
+            break;
+        }
+    }
+    contacts.len()
+}
+
+/// Position constraint pass once per tick after integration, so boids never
+/// interpenetrate. Running per tick keeps the contact count independent of the frame rate.
+pub fn resolve_collisions(
+    timer: Res<BoidTimer>,
+    settings: Res<BoidSettings>,
+    mut contacts: ResMut<Contacts>,
+    mut boids: Query<&mut Position, With<Boid>>,
+) {
+    if !timer.finished() || settings.collision_iterations == 0 {
+        return;
+    }
+    let mut positions: Vec<Vec2> = boids.iter().map(|position| position.0).collect();
+    let resolved = resolve_overlaps(
+        &mut positions,
+        settings.boid_radius,
+        settings.collision_iterations,
+    );
+    if resolved == 0 {
+        return;
+    }
+    contacts.0 += resolved;
+    for (mut position, resolved) in boids.iter_mut().zip(positions) {
+        if position.0 != resolved {
+            position.0 = resolved;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn separates_coincident_boids() {
+        let mut positions = vec![Vec2::new(10.0, 10.0), Vec2::new(10.0, 10.0)];
+        let contacts = resolve_overlaps(&mut positions, 5.0, 1);
+        assert_eq!(contacts, 1);
+        assert!(positions.iter().all(|position| position.is_finite()));
+        assert!((positions[0].distance(positions[1]) - 10.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn counts_each_resolved_overlap() {
+        let mut positions = vec![
+            Vec2::new(0.0, 0.0),
+            Vec2::new(4.0, 0.0),
+            Vec2::new(100.0, 0.0),
+            Vec2::new(100.0, 6.0),
+        ];
+        assert_eq!(resolve_overlaps(&mut positions, 5.0, 3), 2);
+        assert!(positions[0].distance(positions[1]) >= 10.0 - 1e-4);
+        assert!(positions[2].distance(positions[3]) >= 10.0 - 1e-4);
+        // nothing left to resolve
+        assert_eq!(resolve_overlaps(&mut positions, 5.0, 3), 0);
+    }
+
+    /// Number of pairs closer than two boid radii.
+    fn count_overlaps(positions: &[Vec2], boid_radius: f32) -> usize {
+        let mut overlaps = 0;
+        for i in 0..positions.len() {
+            for j in (i + 1)..positions.len() {
+                if positions[i].distance(positions[j]) < boid_radius * 2.0 - 1e-4 {
+                    overlaps += 1;
+                }
+            }
+        }
+        overlaps
+    }
+
+    #[test]
+    fn stops_after_the_iteration_cap() {
+        // a tight row, every push creates new overlaps with the neighbors
+        let row: Vec<Vec2> = (0..5).map(|i| Vec2::new(i as f32, 0.0)).collect();
+        let mut once = row.clone();
+        let mut many = row.clone();
+        resolve_overlaps(&mut once, 5.0, 1);
+        resolve_overlaps(&mut many, 5.0, 50);
+        assert!(count_overlaps(&once, 5.0) > 0);
+        assert!(count_overlaps(&many, 5.0) < count_overlaps(&once, 5.0));
+        assert_eq!(resolve_overlaps(&mut row.clone(), 5.0, 0), 0);
+    }
+
+    #[test]
+    fn counts_pairs_once_over_iterations() {
+        // two boids pushed apart in a single pass are one contact, however many passes run
+        let pair = vec![Vec2::ZERO, Vec2::new(1.0, 0.0)];
+        assert_eq!(resolve_overlaps(&mut pair.clone(), 5.0, 1), 1);
+        assert_eq!(resolve_overlaps(&mut pair.clone(), 5.0, 10), 1);
+
+        // a tight row needs several passes, each pair still counts at most once
+        let row: Vec<Vec2> = (0..5).map(|i| Vec2::new(i as f32, 0.0)).collect();
+        let once = resolve_overlaps(&mut row.clone(), 5.0, 1);
+        let many = resolve_overlaps(&mut row.clone(), 5.0, 50);
+        assert!(many >= once);
+        assert!(many <= 5 * 4 / 2);
+    }
+}
@@ -0,0 +1,111 @@
+use bevy::prelude::Color;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Colormap {
+    Viridis,
+    Magma,
+    /// Cyclic hue wheel, suited for angles.
+    Rainbow,
+    Grayscale,
+    /// Ten distinct colors, suited for ids.
+    Categorical,
+}
+
+impl Colormap {
+    pub const ALL: [Colormap; 5] = [
+        Colormap::Viridis,
+        Colormap::Magma,
+        Colormap::Rainbow,
+        Colormap::Grayscale,
+        Colormap::Categorical,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Colormap::Viridis => "Viridis",
+            Colormap::Magma => "Magma",
+            Colormap::Rainbow => "Rainbow",
+            Colormap::Grayscale => "Grayscale",
+            Colormap::Categorical => "Categorical",
+        }
+    }
+
+    /// Color for a value between 0.0 and 1.0, values outside are clamped.
+    pub fn sample(&self, value: f32) -> Color {
+        let value = if value.is_nan() {
+            0.0
+        } else {
+            value.clamp(0.0, 1.0)
+        };
+        match self {
+            Colormap::Viridis => interpolate(&VIRIDIS, value),
+            Colormap::Magma => interpolate(&MAGMA, value),
+            Colormap::Rainbow => Color::hsl(value * 360.0, 0.9, 0.5),
+            Colormap::Grayscale => Color::rgb(value, value, value),
+            Colormap::Categorical => {
+                let index = (value * (CATEGORICAL.len() - 1) as f32).round() as usize;
+                self.category(index)
+            }
+        }
+    }
+
+    /// Number of distinct id colors, ids wrap around after it.
+    pub fn category_count(&self) -> usize {
+        match self {
+            Colormap::Categorical => CATEGORICAL.len(),
+            _ => 20,
+        }
+    }
+
+    /// Color for a discrete id, modulo `category_count`.
+    pub fn category(&self, index: usize) -> Color {
+        let index = index % self.category_count();
+        match self {
+            Colormap::Categorical => {
+                let [r, g, b] = CATEGORICAL[index];
+                Color::rgb_u8(r, g, b)
+            }
+            // spread ids over the map with the golden ratio so neighbors differ
+            _ => self.sample((index as f32 * 0.618_034).fract()),
+        }
+    }
+}
+
+const VIRIDIS: [[u8; 3]; 5] = [
+    [68, 1, 84],
+    [59, 82, 139],
+    [33, 145, 140],
+    [94, 201, 98],
+    [253, 231, 37],
+];
+
+const MAGMA: [[u8; 3]; 5] = [
+    [0, 0, 4],
+    [81, 18, 124],
+    [183, 55, 121],
+    [252, 137, 97],
+    [252, 253, 191],
+];
+
+const CATEGORICAL: [[u8; 3]; 10] = [
+    [31, 119, 180],
+    [255, 127, 14],
+    [44, 160, 44],
+    [214, 39, 40],
+    [148, 103, 189],
+    [140, 86, 75],
+    [227, 119, 194],
+    [127, 127, 127],
+    [188, 189, 34],
+    [23, 190, 207],
+];
+
+fn interpolate(stops: &[[u8; 3]], value: f32) -> Color {
+    let position = value * (stops.len() - 1) as f32;
+    let index = (position.floor() as usize).min(stops.len() - 2);
+    let t = position - index as f32;
+    let [r0, g0, b0] = stops[index];
+    let [r1, g1, b1] = stops[index + 1];
+    let lerp = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t) / 255.0;
+    Color::rgb(lerp(r0, r1), lerp(g0, g1), lerp(b0, b1))
+}
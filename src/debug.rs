@@ -0,0 +1,133 @@
+use bevy::prelude::{Color, Entity, Gizmos, Query, Res, Resource, Vec2, With};
+
+use crate::boids::{Boid, BoidSettings, Position, SteeringForces};
+use crate::params::BoidParams;
+use crate::physics::PhysicsSettings;
+use crate::picking::SelectedBoid;
+
+/// Draws the perception radii, counted neighbors and steering forces of the selected boid.
+#[derive(Resource)]
+pub struct DebugOverlay {
+    pub enabled: bool,
+    /// Length in pixels of a force of 1.0, shared by all forces so they stay comparable.
+    pub force_scale: f32,
+}
+
+impl Default for DebugOverlay {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            force_scale: 1000.0,
+        }
+    }
+}
+
+const SEPARATION_COLOR: Color = Color::RED;
+const ALIGNMENT_COLOR: Color = Color::GREEN;
+const COHESION_COLOR: Color = Color::BLUE;
+const COLLISION_COLOR: Color = Color::ORANGE;
+const SEEK_COLOR: Color = Color::PURPLE;
+const WANDER_COLOR: Color = Color::YELLOW;
+const FOLLOW_COLOR: Color = Color::PINK;
+const PATH_COLOR: Color = Color::TEAL;
+const FLOW_COLOR: Color = Color::OLIVE;
+const BOUNDARY_COLOR: Color = Color::CYAN;
+const WIND_COLOR: Color = Color::WHITE;
+const DRAG_COLOR: Color = Color::GRAY;
+
+pub fn draw_arrow(gizmos: &mut Gizmos, start: Vec2, vector: Vec2, color: Color) {
+    if vector.length() < 0.5 {
+        return;
+    }
+    let end = start + vector;
+    let head = vector.normalize() * vector.length().min(8.0);
+    gizmos.line_2d(start, end, color);
+    gizmos.line_2d(end, end - head + head.perp() * 0.5, color);
+    gizmos.line_2d(end, end - head - head.perp() * 0.5, color);
+}
+
+pub fn draw_debug_overlay(
+    overlay: Res<DebugOverlay>,
+    selected: Res<SelectedBoid>,
+    settings: Res<BoidSettings>,
+    physics: Res<PhysicsSettings>,
+    mut gizmos: Gizmos,
+    boids: Query<(Entity, &Position, &SteeringForces, Option<&BoidParams>), With<Boid>>,
+) {
+    if !overlay.enabled {
+        return;
+    }
+    // without a picked boid the overlay inspects any boid
+    let Some((entity, position, forces, params)) = selected
+        .0
+        .and_then(|entity| boids.get(entity).ok())
+        .or_else(|| boids.iter().next())
+    else {
+        return;
+    };
+    let position = position.0;
+    // the radii the boid actually flocks with, its own ones if it has any
+    let params = params
+        .copied()
+        .unwrap_or_else(|| BoidParams::from_settings(&settings, &physics));
+
+    gizmos.circle_2d(position, params.separation_radius, SEPARATION_COLOR);
+    gizmos.circle_2d(position, params.alignment_radius, ALIGNMENT_COLOR);
+    gizmos.circle_2d(position, params.cohesion_radius, COHESION_COLOR);
+
+    // one line per counted neighbor, colored by the smallest radius it is inside of
+    let mut radii = [
+        (params.separation_radius, SEPARATION_COLOR),
+        (params.alignment_radius, ALIGNMENT_COLOR),
+        (params.cohesion_radius, COHESION_COLOR),
+    ];
+    radii.sort_by(|a, b| a.0.total_cmp(&b.0));
+    for (other_entity, other_position, _, _) in boids.iter() {
+        if other_entity == entity {
+            continue;
+        }
+        let distance = position.distance(other_position.0);
+        if let Some((_, color)) = radii.iter().find(|(radius, _)| distance < *radius) {
+            gizmos.line_2d(position, other_position.0, color.with_a(0.5));
+        }
+    }
+
+    let scale = overlay.force_scale;
+    draw_arrow(
+        &mut gizmos,
+        position,
+        forces.separation * scale,
+        SEPARATION_COLOR,
+    );
+    draw_arrow(
+        &mut gizmos,
+        position,
+        forces.alignment * scale,
+        ALIGNMENT_COLOR,
+    );
+    draw_arrow(
+        &mut gizmos,
+        position,
+        forces.cohesion * scale,
+        COHESION_COLOR,
+    );
+    draw_arrow(
+        &mut gizmos,
+        position,
+        forces.collision * scale,
+        COLLISION_COLOR,
+    );
+    draw_arrow(&mut gizmos, position, forces.seek * scale, SEEK_COLOR);
+    draw_arrow(&mut gizmos, position, forces.wander * scale, WANDER_COLOR);
+    draw_arrow(&mut gizmos, position, forces.follow * scale, FOLLOW_COLOR);
+    draw_arrow(&mut gizmos, position, forces.path * scale, PATH_COLOR);
+    draw_arrow(&mut gizmos, position, forces.flow * scale, FLOW_COLOR);
+    draw_arrow(
+        &mut gizmos,
+        position,
+        forces.boundary * scale,
+        BOUNDARY_COLOR,
+    );
+    draw_arrow(&mut gizmos, position, forces.wind * scale, WIND_COLOR);
+    draw_arrow(&mut gizmos, position, forces.drag * scale, DRAG_COLOR);
+}
@@ -0,0 +1,213 @@
+use std::{
+    collections::HashSet,
+    fs::{File, OpenOptions},
+    io::{BufWriter, Write},
+};
+
+use bevy::prelude::{error, info, DetectChanges, Entity, Query, Res, ResMut, Resource, With};
+
+use crate::boids::{Boid, BoidTimer, Position, Velocity};
+use crate::metrics::FlockMetrics;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Comma separated values with a header row.
+    Csv,
+    /// Newline-delimited JSON, one object per row.
+    Json,
+}
+
+impl ExportFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "csv" => Some(Self::Csv),
+            "json" | "ndjson" => Some(Self::Json),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Json => "jsonl",
+        }
+    }
+}
+
+/// Where and how per-tick data is written, changing it reopens the files. A file is
+/// truncated the first time it is opened in a run, reopening it appends.
+#[derive(Debug, Resource)]
+pub struct ExportSettings {
+    pub enabled: bool,
+    pub format: ExportFormat,
+    pub metrics_path: String,
+    /// Also write one row per boid and tick.
+    pub include_boids: bool,
+    pub boids_path: String,
+}
+
+impl Default for ExportSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            format: ExportFormat::Csv,
+            metrics_path: "metrics.csv".into(),
+            include_boids: false,
+            boids_path: "boids.csv".into(),
+        }
+    }
+}
+
+#[derive(Default, Resource)]
+pub struct ExportWriters {
+    metrics: Option<BufWriter<File>>,
+    boids: Option<BufWriter<File>>,
+    /// Paths opened during this run, reopening them appends instead of truncating.
+    opened: HashSet<String>,
+}
+
+impl ExportWriters {
+    /// Closes the files, they are reopened with the current settings on the next tick.
+    fn close(&mut self) {
+        self.metrics = None;
+        self.boids = None;
+    }
+}
+
+const METRICS_COLUMNS: [&str; 6] = [
+    "tick",
+    "polarization",
+    "milling",
+    "density",
+    "speed",
+    "contacts",
+];
+const BOID_COLUMNS: [&str; 6] = ["tick", "boid", "x", "y", "vx", "vy"];
+
+fn create_writer(
+    path: &str,
+    format: ExportFormat,
+    columns: &[&str],
+    opened: &mut HashSet<String>,
+) -> std::io::Result<BufWriter<File>> {
+    let file = if opened.contains(path) {
+        OpenOptions::new().append(true).create(true).open(path)?
+    } else {
+        File::create(path)?
+    };
+    let is_empty = file.metadata()?.len() == 0;
+    let mut writer = BufWriter::new(file);
+    if format == ExportFormat::Csv && is_empty {
+        writeln!(writer, "{}", columns.join(","))?;
+    }
+    opened.insert(path.to_string());
+    info!("exporting to {}", path);
+    Ok(writer)
+}
+
+fn write_row(
+    writer: &mut impl Write,
+    format: ExportFormat,
+    columns: &[&str],
+    values: &[String],
+) -> std::io::Result<()> {
+    match format {
+        ExportFormat::Csv => writeln!(writer, "{}", values.join(",")),
+        ExportFormat::Json => {
+            let fields: Vec<String> = columns
+                .iter()
+                .zip(values)
+                .map(|(column, value)| match value.as_str() {
+                    // JSON has no NaN or infinity
+                    "NaN" | "inf" | "-inf" => format!("\"{}\":null", column),
+                    _ => format!("\"{}\":{}", column, value),
+                })
+                .collect();
+            writeln!(writer, "{{{}}}", fields.join(","))
+        }
+    }
+}
+
+fn write_tick(
+    settings: &ExportSettings,
+    writers: &mut ExportWriters,
+    metrics: &FlockMetrics,
+    boids: &Query<(Entity, &Position, &Velocity), With<Boid>>,
+) -> std::io::Result<()> {
+    if writers.metrics.is_none() {
+        writers.metrics = Some(create_writer(
+            &settings.metrics_path,
+            settings.format,
+            &METRICS_COLUMNS,
+            &mut writers.opened,
+        )?);
+    }
+    if let Some(writer) = writers.metrics.as_mut() {
+        write_row(
+            writer,
+            settings.format,
+            &METRICS_COLUMNS,
+            &[
+                metrics.tick.to_string(),
+                metrics.polarization.to_string(),
+                metrics.milling.to_string(),
+                metrics.density.to_string(),
+                metrics.speed.to_string(),
+                metrics.contacts.to_string(),
+            ],
+        )?;
+        writer.flush()?;
+    }
+
+    if !settings.include_boids {
+        return Ok(());
+    }
+    if writers.boids.is_none() {
+        writers.boids = Some(create_writer(
+            &settings.boids_path,
+            settings.format,
+            &BOID_COLUMNS,
+            &mut writers.opened,
+        )?);
+    }
+    if let Some(writer) = writers.boids.as_mut() {
+        for (entity, position, velocity) in boids.iter() {
+            write_row(
+                writer,
+                settings.format,
+                &BOID_COLUMNS,
+                &[
+                    metrics.tick.to_string(),
+                    entity.index().to_string(),
+                    position.0.x.to_string(),
+                    position.0.y.to_string(),
+                    velocity.0.x.to_string(),
+                    velocity.0.y.to_string(),
+                ],
+            )?;
+        }
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+pub fn export_data(
+    timer: Res<BoidTimer>,
+    metrics: Res<FlockMetrics>,
+    mut settings: ResMut<ExportSettings>,
+    mut writers: ResMut<ExportWriters>,
+    boids: Query<(Entity, &Position, &Velocity), With<Boid>>,
+) {
+    if settings.is_changed() {
+        // close the previous files, they get reopened with the new settings
+        writers.close();
+    }
+    if !settings.enabled || !timer.finished() {
+        return;
+    }
+
+    if let Err(err) = write_tick(&settings, &mut writers, &metrics, &boids) {
+        error!("export failed, disabling: {}", err);
+        settings.enabled = false;
+    }
+}
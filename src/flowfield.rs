@@ -0,0 +1,352 @@
+use std::fmt::Write as _;
+
+use bevy::{
+    prelude::{
+        Camera, GlobalTransform, Input, KeyCode, Local, MouseButton, Query, Res, ResMut, Resource,
+        Vec2, With,
+    },
+    window::{PrimaryWindow, Window},
+};
+use bevy_egui::EguiContexts;
+
+use crate::boids::BoidSettings;
+use crate::noise::curl_noise;
+use crate::render::{get_cursor_world_position, MainCamera2d};
+
+/// Grid of unit or shorter vectors covering the arena that boids align to.
+#[derive(Resource)]
+pub struct FlowField {
+    pub min: Vec2,
+    pub cell_size: f32,
+    pub width: usize,
+    pub height: usize,
+    /// Vector per cell, row by row starting at the bottom of the arena.
+    pub vectors: Vec<Vec2>,
+}
+
+impl FlowField {
+    pub fn from_settings(settings: &BoidSettings, cell_size: f32) -> Self {
+        let min = Vec2::new(settings.boundary_min_x, settings.boundary_min_y);
+        let width =
+            ((settings.boundary_max_x - settings.boundary_min_x) / cell_size).ceil() as usize;
+        let height =
+            ((settings.boundary_max_y - settings.boundary_min_y) / cell_size).ceil() as usize;
+        Self {
+            min,
+            cell_size,
+            width,
+            height,
+            vectors: vec![Vec2::ZERO; width * height],
+        }
+    }
+
+    pub fn cell_center(&self, index: usize) -> Vec2 {
+        let cell = Vec2::new((index % self.width) as f32, (index / self.width) as f32);
+        self.min + (cell + 0.5) * self.cell_size
+    }
+
+    /// Field at a position, interpolated between the four closest cell centers.
+    /// Zero outside of the grid.
+    pub fn sample(&self, position: Vec2) -> Vec2 {
+        let cell = (position - self.min) / self.cell_size - 0.5;
+        let size = Vec2::new(self.width as f32, self.height as f32);
+        if self.vectors.is_empty()
+            || cell.x < -0.5
+            || cell.y < -0.5
+            || cell.x > size.x - 0.5
+            || cell.y > size.y - 0.5
+        {
+            return Vec2::ZERO;
+        }
+        let cell = cell.clamp(Vec2::ZERO, size - 1.0);
+        let (x, y) = (cell.x as usize, cell.y as usize);
+        let (x1, y1) = ((x + 1).min(self.width - 1), (y + 1).min(self.height - 1));
+        let t = cell - Vec2::new(x as f32, y as f32);
+        let vector = |x: usize, y: usize| self.vectors[y * self.width + x];
+        let bottom = vector(x, y).lerp(vector(x1, y), t.x);
+        let top = vector(x, y1).lerp(vector(x1, y1), t.x);
+        bottom.lerp(top, t.y)
+    }
+
+    pub fn clear(&mut self) {
+        self.vectors
+            .iter_mut()
+            .for_each(|vector| *vector = Vec2::ZERO);
+    }
+
+    /// Swirling field from curl noise, scale is the size of a swirl in pixels.
+    pub fn fill_curl_noise(&mut self, seed: u32, scale: f32) {
+        for index in 0..self.vectors.len() {
+            let position = self.cell_center(index) / scale;
+            self.vectors[index] = curl_noise(seed, position);
+        }
+        // normalize so the strongest cell has length 1.0
+        let max = self
+            .vectors
+            .iter()
+            .map(|vector| vector.length())
+            .fold(0.0, f32::max);
+        if max > 0.0 {
+            self.vectors.iter_mut().for_each(|vector| *vector /= max);
+        }
+    }
+
+    /// Field circling counterclockwise around a center, pull bends it inwards,
+    /// negative pull outwards.
+    pub fn fill_vortex(&mut self, center: Vec2, pull: f32) {
+        for index in 0..self.vectors.len() {
+            let radial = (center - self.cell_center(index)).normalize_or_zero();
+            self.vectors[index] = (-radial.perp() + radial * pull).normalize_or_zero();
+        }
+    }
+
+    /// Sets the cells within the radius towards the direction, fading out at the edge.
+    pub fn paint(&mut self, position: Vec2, direction: Vec2, radius: f32) {
+        let direction = direction.normalize_or_zero();
+        for index in 0..self.vectors.len() {
+            let distance = self.cell_center(index).distance(position);
+            if distance < radius {
+                let strength = 1.0 - distance / radius;
+                self.vectors[index] = self.vectors[index].lerp(direction, strength);
+            }
+        }
+    }
+
+    /// Writes the field as text, a header line `width height cell_size min_x min_y`
+    /// followed by one `x y` line per cell.
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let mut text = String::new();
+        let _ = writeln!(
+            text,
+            "{} {} {} {} {}",
+            self.width, self.height, self.cell_size, self.min.x, self.min.y
+        );
+        for vector in self.vectors.iter() {
+            let _ = writeln!(text, "{} {}", vector.x, vector.y);
+        }
+        std::fs::write(path, text).map_err(|err| format!("could not write {}: {}", path, err))
+    }
+
+    /// Reads a field written by `save`, values may be separated by any whitespace.
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|err| format!("could not read {}: {}", path, err))?;
+        let invalid = |line: usize| format!("invalid flow field {}, line {}", path, line + 1);
+        let mut lines = text.lines().enumerate();
+
+        let (_, header) = lines.next().ok_or_else(|| invalid(0))?;
+        let header: Vec<&str> = header.split_whitespace().collect();
+        let [width, height, cell_size, min_x, min_y] = header[..] else {
+            return Err(invalid(0));
+        };
+        let width: usize = width.parse().map_err(|_| invalid(0))?;
+        let height: usize = height.parse().map_err(|_| invalid(0))?;
+        let cell_size: f32 = cell_size.parse().map_err(|_| invalid(0))?;
+        let min = Vec2::new(
+            min_x.parse().map_err(|_| invalid(0))?,
+            min_y.parse().map_err(|_| invalid(0))?,
+        );
+        // a NaN cell size would slip past `<= 0.0` on its own
+        if !cell_size.is_finite() || cell_size <= 0.0 || !min.is_finite() {
+            return Err(invalid(0));
+        }
+        let cells = width.checked_mul(height).ok_or_else(|| invalid(0))?;
+
+        let vectors = lines
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(number, line)| {
+                let values: Vec<&str> = line.split_whitespace().collect();
+                let [x, y] = values[..] else {
+                    return Err(invalid(number));
+                };
+                let vector = Vec2::new(
+                    x.parse().map_err(|_| invalid(number))?,
+                    y.parse().map_err(|_| invalid(number))?,
+                );
+                if !vector.is_finite() {
+                    return Err(invalid(number));
+                }
+                Ok(vector)
+            })
+            .collect::<Result<Vec<Vec2>, String>>()?;
+        if vectors.len() != cells {
+            return Err(format!(
+                "flow field {} has {} cells, expected {}",
+                path,
+                vectors.len(),
+                cells
+            ));
+        }
+
+        Ok(Self {
+            min,
+            cell_size,
+            width,
+            height,
+            vectors,
+        })
+    }
+}
+
+/// Inputs of the flow field window and the brush used for painting.
+#[derive(Debug, Resource)]
+pub struct FlowFieldSettings {
+    pub brush_radius: f32,
+    pub noise_seed: u32,
+    /// Size of a curl noise swirl in pixels.
+    pub noise_scale: f32,
+    /// How much the vortex bends inwards, negative bends outwards.
+    pub vortex_pull: f32,
+    pub path: String,
+}
+
+impl Default for FlowFieldSettings {
+    fn default() -> Self {
+        Self {
+            brush_radius: 80.0,
+            noise_seed: 0,
+            noise_scale: 400.0,
+            vortex_pull: 0.2,
+            path: "flowfield.txt".into(),
+        }
+    }
+}
+
+/// With alt held, dragging with the middle mouse button paints the field along the drag.
+#[allow(clippy::too_many_arguments)]
+pub fn paint_flow_field(
+    buttons: Res<Input<MouseButton>>,
+    keys: Res<Input<KeyCode>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera_transform: Query<(&Camera, &GlobalTransform), With<MainCamera2d>>,
+    mut contexts: EguiContexts,
+    settings: Res<FlowFieldSettings>,
+    mut field: ResMut<FlowField>,
+    mut last_cursor: Local<Option<Vec2>>,
+) {
+    if !buttons.pressed(MouseButton::Middle)
+        || !keys.any_pressed([KeyCode::AltLeft, KeyCode::AltRight])
+        || contexts.ctx_mut().wants_pointer_input()
+    {
+        *last_cursor = None;
+        return;
+    }
+    let (camera, camera_transform) = camera_transform.single();
+    let window = windows.get_single().unwrap();
+    let Some(cursor) = get_cursor_world_position(window, camera, camera_transform) else {
+        return;
+    };
+
+    if let Some(last) = *last_cursor {
+        if last != cursor {
+            field.paint(cursor, cursor - last, settings.brush_radius);
+        }
+    }
+    *last_cursor = Some(cursor);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Path in the temp directory, unique per test and process.
+    fn temp_path(name: &str) -> String {
+        std::env::temp_dir()
+            .join(format!("flowfield_{}_{}.txt", name, std::process::id()))
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    fn load_text(name: &str, text: &str) -> Result<FlowField, String> {
+        let path = temp_path(name);
+        std::fs::write(&path, text).unwrap();
+        let field = FlowField::load(&path);
+        let _ = std::fs::remove_file(&path);
+        field
+    }
+
+    /// 2 by 2 field with 10 px cells starting at the origin.
+    fn square_field() -> FlowField {
+        FlowField {
+            min: Vec2::ZERO,
+            cell_size: 10.0,
+            width: 2,
+            height: 2,
+            vectors: vec![
+                Vec2::new(1.0, 0.0),
+                Vec2::new(0.0, 1.0),
+                Vec2::new(-1.0, 0.0),
+                Vec2::new(0.0, -1.0),
+            ],
+        }
+    }
+
+    #[test]
+    fn round_trips_through_save_and_load() {
+        let mut field = square_field();
+        field.min = Vec2::new(-12.5, 3.0);
+        field.vectors[2] = Vec2::new(0.25, -0.125);
+        let path = temp_path("round_trip");
+        field.save(&path).unwrap();
+        let loaded = FlowField::load(&path);
+        let _ = std::fs::remove_file(&path);
+
+        let loaded = loaded.unwrap();
+        assert_eq!(loaded.min, field.min);
+        assert_eq!(loaded.cell_size, field.cell_size);
+        assert_eq!((loaded.width, loaded.height), (2, 2));
+        assert_eq!(loaded.vectors, field.vectors);
+    }
+
+    #[test]
+    fn loads_tab_separated_values() {
+        let field = load_text("tabs", "1\t1\t5.0\t0\t0\n0.5\t-0.5\n").unwrap();
+        assert_eq!(field.vectors, vec![Vec2::new(0.5, -0.5)]);
+    }
+
+    #[test]
+    fn rejects_invalid_headers() {
+        assert!(load_text("nan_size", "1 1 NaN 0 0\n0 0\n").is_err());
+        assert!(load_text("zero_size", "1 1 0 0 0\n0 0\n").is_err());
+        assert!(load_text("inf_min", "1 1 5 inf 0\n0 0\n").is_err());
+        let huge = format!("{} 2 5 0 0\n", usize::MAX);
+        assert!(load_text("overflow", &huge).is_err());
+        assert!(load_text("cell_count", "2 1 5 0 0\n0 0\n").is_err());
+        assert!(load_text("nan_cell", "1 1 5 0 0\nNaN 0\n").is_err());
+    }
+
+    #[test]
+    fn samples_bilinearly_between_cell_centers() {
+        let field = square_field();
+        // cell centers are exact
+        assert_eq!(field.sample(Vec2::new(5.0, 5.0)), Vec2::new(1.0, 0.0));
+        assert_eq!(field.sample(Vec2::new(15.0, 15.0)), Vec2::new(0.0, -1.0));
+        // halfway between the two bottom cells
+        let between = field.sample(Vec2::new(10.0, 5.0));
+        assert!(between.distance(Vec2::new(0.5, 0.5)) < 1e-6);
+        // the middle averages all four cells
+        assert!(field.sample(Vec2::new(10.0, 10.0)).length() < 1e-6);
+        // the border half cells hold the edge value, outside is zero
+        assert_eq!(field.sample(Vec2::new(1.0, 1.0)), Vec2::new(1.0, 0.0));
+        assert_eq!(field.sample(Vec2::new(-1.0, 5.0)), Vec2::ZERO);
+        assert_eq!(field.sample(Vec2::new(5.0, 21.0)), Vec2::ZERO);
+    }
+
+    #[test]
+    fn curl_noise_fill_is_normalized() {
+        let mut field = FlowField {
+            min: Vec2::ZERO,
+            cell_size: 10.0,
+            width: 8,
+            height: 8,
+            vectors: vec![Vec2::ZERO; 64],
+        };
+        field.fill_curl_noise(7, 40.0);
+        let max = field
+            .vectors
+            .iter()
+            .map(|vector| vector.length())
+            .fold(0.0, f32::max);
+        assert!((max - 1.0).abs() < 1e-5);
+    }
+}
@@ -0,0 +1,76 @@
+use bevy::prelude::{Query, Res, ResMut, Resource, Vec2, With};
+
+use crate::boids::{Boid, BoidSettings, BoidTimer, Position};
+
+/// Boid occupancy accumulated over time on a grid covering the arena.
+#[derive(Resource)]
+pub struct Heatmap {
+    pub min: Vec2,
+    pub cell_size: f32,
+    pub width: usize,
+    pub height: usize,
+    /// Occupancy per cell, row by row starting at the bottom of the arena.
+    pub cells: Vec<f32>,
+    /// Factor all cells are multiplied with every tick, 1.0 never forgets.
+    pub decay: f32,
+}
+
+impl Heatmap {
+    pub fn from_settings(settings: &BoidSettings, cell_size: f32) -> Self {
+        let min = Vec2::new(settings.boundary_min_x, settings.boundary_min_y);
+        let width =
+            ((settings.boundary_max_x - settings.boundary_min_x) / cell_size).ceil() as usize;
+        let height =
+            ((settings.boundary_max_y - settings.boundary_min_y) / cell_size).ceil() as usize;
+        Self {
+            min,
+            cell_size,
+            width,
+            height,
+            cells: vec![0.0; width * height],
+            decay: 0.999,
+        }
+    }
+
+    pub fn size(&self) -> Vec2 {
+        Vec2::new(self.width as f32, self.height as f32) * self.cell_size
+    }
+
+    pub fn cell_index(&self, position: Vec2) -> Option<usize> {
+        let cell = ((position - self.min) / self.cell_size).floor();
+        if cell.x < 0.0 || cell.y < 0.0 {
+            return None;
+        }
+        let (x, y) = (cell.x as usize, cell.y as usize);
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        Some(y * self.width + x)
+    }
+
+    pub fn max(&self) -> f32 {
+        self.cells.iter().copied().fold(0.0, f32::max)
+    }
+
+    pub fn reset(&mut self) {
+        self.cells.iter_mut().for_each(|cell| *cell = 0.0);
+    }
+}
+
+pub fn update_heatmap(
+    timer: Res<BoidTimer>,
+    mut heatmap: ResMut<Heatmap>,
+    boids: Query<&Position, With<Boid>>,
+) {
+    if !timer.finished() {
+        return;
+    }
+
+    let decay = heatmap.decay;
+    heatmap.cells.iter_mut().for_each(|cell| *cell *= decay);
+    for position in boids.iter() {
+        if let Some(index) = heatmap.cell_index(position.0) {
+            heatmap.cells[index] += 1.0;
+        }
+    }
+}
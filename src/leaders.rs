@@ -0,0 +1,87 @@
+use bevy::{
+    prelude::{Camera, Component, GlobalTransform, Query, ResMut, Resource, Vec2, With},
+    window::{PrimaryWindow, Window},
+};
+
+use crate::render::{get_cursor_world_position, MainCamera2d};
+
+/// Marks a boid that is followed by the others instead of flocking with them.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct Leader;
+
+/// What steers the leaders.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LeaderDrive {
+    /// Leaders steer towards targets and the route, followers ignore them.
+    Targets,
+    Wander,
+    /// Leaders seek the mouse cursor.
+    Mouse,
+}
+
+impl LeaderDrive {
+    pub const ALL: [LeaderDrive; 3] = [
+        LeaderDrive::Targets,
+        LeaderDrive::Wander,
+        LeaderDrive::Mouse,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LeaderDrive::Targets => "Targets",
+            LeaderDrive::Wander => "Wander",
+            LeaderDrive::Mouse => "Mouse",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "targets" => Some(LeaderDrive::Targets),
+            "wander" => Some(LeaderDrive::Wander),
+            "mouse" => Some(LeaderDrive::Mouse),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Resource)]
+pub struct LeaderSettings {
+    /// Number of boids made leaders when spawning.
+    pub count: u32,
+    pub drive: LeaderDrive,
+    /// Weight of the wander or mouse force of leaders.
+    pub weight: f32,
+    /// Let followers steer towards targets too, instead of only following.
+    pub followers_seek_targets: bool,
+    /// World position of the mouse cursor, for `LeaderDrive::Mouse`.
+    pub cursor: Option<Vec2>,
+}
+
+impl Default for LeaderSettings {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            drive: LeaderDrive::Targets,
+            weight: 1.0,
+            followers_seek_targets: false,
+            cursor: None,
+        }
+    }
+}
+
+pub fn update_leader_cursor(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera_transform: Query<(&Camera, &GlobalTransform), With<MainCamera2d>>,
+    mut leader_settings: ResMut<LeaderSettings>,
+) {
+    if leader_settings.drive != LeaderDrive::Mouse {
+        return;
+    }
+    let (camera, camera_transform) = camera_transform.single();
+    let window = windows.get_single().unwrap();
+    let cursor = get_cursor_world_position(window, camera, camera_transform);
+    // the cursor stays where it left the window
+    if cursor.is_some() && cursor != leader_settings.cursor {
+        leader_settings.cursor = cursor;
+    }
+}
@@ -1,58 +1,322 @@
+use std::time::Duration;
+
+use bevy::app::ScheduleRunnerPlugin;
+use bevy::log::LogPlugin;
 use bevy::window::{PresentMode, Window, WindowResolution};
 use bevy::{app::AppExit, prelude::*};
-use bevy_egui::EguiPlugin;
+use bevy_egui::{EguiContexts, EguiPlugin};
 use bevy_prototype_lyon::prelude::*;
-use boids::{BoidSettings, BoidTimer, TargetPosition};
+use boids::BoidTimer;
+use camera::{CameraSettings, MouseDrag};
+use cli::Args;
+use clusters::Clusters;
+use collisions::Contacts;
+use debug::DebugOverlay;
+use export::ExportWriters;
+use flowfield::FlowFieldSettings;
+use heatmap::Heatmap;
+use metrics::{FlockMetrics, MetricsHistory};
+use paths::PathEditing;
+use picking::SelectedBoid;
+use render::{ColorLegend, RenderSettings};
+use targets::{TargetDrag, TargetSettings};
+use trails::TrailSettings;
 
 mod boids;
+mod boids3d;
+mod camera;
+mod cli;
+mod clusters;
+mod collisions;
+mod colormap;
+mod debug;
+mod export;
+mod flowfield;
+mod heatmap;
+mod leaders;
+mod metrics;
+mod noise;
+mod params;
+mod paths;
+mod physics;
+mod picking;
+mod raster;
 mod render;
+mod render3d;
+mod routes;
+mod scene;
+mod steering;
+mod svg;
+mod targets;
+mod trails;
 mod ui;
+mod wind;
+
+/// Stops the app once the simulation ran for the given number of ticks.
+#[derive(Resource)]
+pub struct TickLimit(pub Option<u64>);
 
-pub fn quit_on_escape(mut exit: EventWriter<AppExit>, key: Res<Input<KeyCode>>) {
+pub fn quit_on_escape(
+    mut exit: EventWriter<AppExit>,
+    key: Res<Input<KeyCode>>,
+    mut contexts: EguiContexts,
+) {
+    // typing a path into a text field must not quit
+    if contexts.ctx_mut().wants_keyboard_input() {
+        return;
+    }
     if key.just_pressed(KeyCode::Escape) || key.just_pressed(KeyCode::Q) {
         exit.send(AppExit);
     }
 }
 
-fn main() {
+pub fn quit_after_ticks(
+    mut exit: EventWriter<AppExit>,
+    limit: Res<TickLimit>,
+    metrics: Res<FlockMetrics>,
+) {
+    if let Some(limit) = limit.0 {
+        if metrics.tick >= limit {
+            info!("reached {} ticks", limit);
+            exit.send(AppExit);
+        }
+    }
+}
+
+/// Resources and systems shared by the windowed and the headless app.
+fn add_simulation(app: &mut App, args: &Args) {
+    let settings = args.boid_settings();
+    let physics = args.physics_settings();
+    let param_settings = args.boid_param_settings(&settings, &physics);
+    let flow_field = match args.flow_field(&settings) {
+        Ok(field) => field,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(2);
+        }
+    };
+    app.insert_resource(BoidTimer::default())
+        .insert_resource(Heatmap::from_settings(&settings, 20.0))
+        .insert_resource(flow_field)
+        .insert_resource(settings)
+        .insert_resource(FlockMetrics::default())
+        .insert_resource(Contacts::default())
+        .insert_resource(MetricsHistory::default())
+        .insert_resource(Clusters::default())
+        .insert_resource(args.export_settings())
+        .insert_resource(ExportWriters::default())
+        .insert_resource(args.svg_export())
+        .insert_resource(args.png_export())
+        .insert_resource(args.route())
+        .insert_resource(args.leader_settings())
+        .insert_resource(args.wind())
+        .insert_resource(param_settings)
+        .insert_resource(physics)
+        .insert_resource(TickLimit(args.ticks))
+        .add_systems(Startup, boids::setup_boids)
+        .add_systems(Update, boids::apply_boid_velocity)
+        .add_systems(Update, targets::move_targets)
+        .add_systems(Update, boids::update)
+        .add_systems(
+            Update,
+            collisions::resolve_collisions
+                .after(boids::apply_boid_velocity)
+                .after(boids::update)
+                .before(metrics::update_metrics),
+        )
+        .add_systems(Update, metrics::update_metrics.after(boids::update))
+        .add_systems(Update, clusters::update_clusters.after(boids::update))
+        .add_systems(Update, heatmap::update_heatmap.after(boids::update))
+        .add_systems(Update, routes::update_route.after(boids::update))
+        .add_systems(Update, wind::update_wind.after(boids::update))
+        .add_systems(Update, export::export_data.after(metrics::update_metrics))
+        .add_systems(Update, svg::export_svg.after(metrics::update_metrics))
+        .add_systems(Update, raster::export_png.after(metrics::update_metrics))
+        .add_systems(
+            Update,
+            quit_after_ticks
+                .after(export::export_data)
+                .after(svg::export_svg)
+                .after(raster::export_png),
+        );
+}
+
+/// Resources and systems of the 3D simulation, shared by the windowed and the headless app.
+fn add_simulation_3d(app: &mut App, args: &Args) {
+    app.insert_resource(BoidTimer::default())
+        .insert_resource(args.boid_settings())
+        .insert_resource(args.boids_3d_settings())
+        .insert_resource(FlockMetrics::default())
+        .insert_resource(MetricsHistory::default())
+        .insert_resource(args.export_settings())
+        .insert_resource(ExportWriters::default())
+        .insert_resource(TickLimit(args.ticks))
+        .add_systems(Startup, boids3d::setup_boids_3d)
+        .add_systems(Update, boids3d::apply_boid_velocity_3d)
+        .add_systems(Update, boids3d::update_3d)
+        .add_systems(Update, metrics::update_metrics_3d.after(boids3d::update_3d))
+        .add_systems(
+            Update,
+            export::export_data.after(metrics::update_metrics_3d),
+        )
+        .add_systems(Update, quit_after_ticks.after(export::export_data));
+}
+
+fn run_headless(args: Args) {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins.set(ScheduleRunnerPlugin::run_loop(Duration::from_millis(1))))
+        .add_plugins(LogPlugin::default());
+    if args.three_d {
+        add_simulation_3d(&mut app, &args);
+    } else {
+        add_simulation(&mut app, &args);
+    }
+    app.run();
+}
+
+fn primary_window() -> Window {
     let screen_width = 1280.;
     let screen_height = 1280.;
     let window_scaling_factor = 1.0;
     let present_mode = PresentMode::AutoNoVsync; // PresentMode::AutoNoVsync
-    App::new()
-        .add_plugins(
-            DefaultPlugins.set(WindowPlugin {
-                primary_window: Some(Window {
-                    title: "Bevy Boids".into(),
-                    resolution: WindowResolution::new(screen_width, screen_height)
-                        .with_scale_factor_override(window_scaling_factor),
-                    present_mode,
-                    // Tells wasm to resize the window according to the available canvas
-                    fit_canvas_to_parent: true,
-                    // Tells wasm not to override default event handling, like F5, Ctrl+R etc.
-                    prevent_default_event_handling: false,
-                    ..Default::default()
-                }),
-
-                ..Default::default()
-            }),
-        )
-        .add_plugins(ShapePlugin)
-        .insert_resource(TargetPosition::default())
-        .insert_resource(BoidTimer::default())
-        .insert_resource(BoidSettings::default())
-        .add_plugins(EguiPlugin)
-        .add_systems(Startup, boids::setup_boids)
+    Window {
+        title: "Bevy Boids".into(),
+        resolution: WindowResolution::new(screen_width, screen_height)
+            .with_scale_factor_override(window_scaling_factor),
+        present_mode,
+        // Tells wasm to resize the window according to the available canvas
+        fit_canvas_to_parent: true,
+        // Tells wasm not to override default event handling, like F5, Ctrl+R etc.
+        prevent_default_event_handling: false,
+        ..Default::default()
+    }
+}
+
+fn run_windowed_3d(args: Args) {
+    let mut app = App::new();
+    app.add_plugins(DefaultPlugins.set(WindowPlugin {
+        primary_window: Some(primary_window()),
+        ..Default::default()
+    }))
+    .add_plugins(EguiPlugin);
+    add_simulation_3d(&mut app, &args);
+    app.add_systems(Startup, render3d::setup_render_3d)
+        .add_systems(Update, ui::update_ui)
+        .add_systems(Update, ui::update_metrics_ui)
+        .add_systems(Update, ui::update_3d_ui)
+        .add_systems(Update, render3d::spawn_boid_meshes)
+        .add_systems(
+            Update,
+            render3d::update_boid_transforms_3d.after(boids3d::apply_boid_velocity_3d),
+        )
+        .add_systems(Update, render3d::draw_bounds_3d)
+        .add_systems(Update, render3d::update_orbit_camera)
+        .add_systems(Update, quit_on_escape)
+        .run();
+}
+
+fn run_windowed(args: Args) {
+    let mut app = App::new();
+    app.add_plugins(DefaultPlugins.set(WindowPlugin {
+        primary_window: Some(primary_window()),
+        ..Default::default()
+    }))
+    .add_plugins(ShapePlugin)
+    .add_plugins(EguiPlugin);
+    add_simulation(&mut app, &args);
+    app.insert_resource(RenderSettings::default())
+        .insert_resource(ColorLegend::default())
+        .insert_resource(DebugOverlay::default())
+        .insert_resource(SelectedBoid::default())
+        .insert_resource(CameraSettings::default())
+        .insert_resource(MouseDrag::default())
+        .insert_resource(TrailSettings::default())
+        .insert_resource(TargetSettings::default())
+        .insert_resource(TargetDrag::default())
+        .insert_resource(PathEditing::default())
+        .insert_resource(FlowFieldSettings::default())
         .add_systems(Startup, render::setup_camera)
         .add_systems(Startup, render::setup_render)
         .add_systems(Update, ui::update_ui)
+        .add_systems(Update, ui::update_metrics_ui)
+        .add_systems(Update, ui::update_export_ui)
+        .add_systems(Update, ui::update_render_ui)
+        .add_systems(Update, ui::update_inspector_ui)
+        .add_systems(Update, ui::update_targets_ui)
+        .add_systems(Update, ui::update_route_ui)
+        .add_systems(Update, ui::update_leaders_ui)
+        .add_systems(Update, ui::update_paths_ui)
+        .add_systems(Update, ui::update_flow_field_ui)
+        .add_systems(Update, ui::update_wind_ui)
+        .add_systems(Update, ui::update_params_ui)
+        .add_systems(Update, ui::update_physics_ui)
         .add_systems(Update, render::spawn_boid_renderable)
-        .add_systems(Update, render::update_boid_renderable_transform)
-        .add_systems(Update, render::update_boid_target_renderable_transform)
+        .add_systems(
+            Update,
+            render::record_interpolation_state.after(boids::update),
+        )
+        .add_systems(
+            Update,
+            render::update_boid_renderable_transform.after(render::record_interpolation_state),
+        )
+        .add_systems(Update, render::update_boid_renderable_color)
+        .add_systems(
+            Update,
+            render::update_boid_renderable_stroke.after(render::update_boid_renderable_color),
+        )
+        .add_systems(Update, render::update_render_backend)
+        .add_systems(
+            Update,
+            render::update_batched_boids_renderable
+                .after(render::update_boid_renderable_color)
+                .after(render::record_interpolation_state),
+        )
+        .add_systems(Update, render::update_heatmap_renderable)
+        .add_systems(Update, debug::draw_debug_overlay)
+        .add_systems(Update, picking::draw_selected_boid)
+        .add_systems(Update, trails::update_trails.after(boids::update))
+        .add_systems(Update, render::draw_trails)
+        .add_systems(Update, render::spawn_target_renderable)
+        .add_systems(Update, render::update_target_renderable)
+        .add_systems(Update, render::draw_target_influence)
+        .add_systems(Update, render::draw_route)
+        .add_systems(Update, render::draw_leaders)
+        .add_systems(Update, render::draw_paths)
+        .add_systems(Update, render::draw_flow_field)
         .add_systems(Update, boids::respawn_boids)
-        .add_systems(Update, boids::update_target_from_mouse_click)
-        .add_systems(Update, boids::apply_boid_velocity)
-        .add_systems(Update, boids::update)
+        .add_systems(Update, targets::update_targets_from_mouse)
+        .add_systems(Update, routes::update_route_from_mouse)
+        .add_systems(Update, leaders::update_leader_cursor)
+        .add_systems(Update, paths::update_paths_from_mouse)
+        .add_systems(Update, flowfield::paint_flow_field)
+        .add_systems(Update, camera::update_camera_from_input)
+        .add_systems(
+            Update,
+            picking::select_boid_from_mouse_click.after(camera::update_camera_from_input),
+        )
+        .add_systems(
+            Update,
+            camera::follow_camera.after(boids::apply_boid_velocity),
+        )
         .add_systems(Update, quit_on_escape)
         .run();
 }
+
+fn main() {
+    let args = match Args::parse(std::env::args()) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+    if args.help {
+        println!("{}", cli::USAGE);
+    } else if args.headless {
+        run_headless(args);
+    } else if args.three_d {
+        run_windowed_3d(args);
+    } else {
+        run_windowed(args);
+    }
+}
@@ -0,0 +1,169 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::{Query, Res, ResMut, Resource, Vec2, Vec3, With};
+
+use crate::boids::{count_neighbors, Boid, BoidSettings, BoidTimer, Position, Velocity};
+use crate::boids3d::{Boid3d, Position3d, Velocity3d};
+use crate::collisions::Contacts;
+use crate::steering::SteeringVector;
+
+/// Aggregate state of the flock, sampled once per simulation tick.
+#[derive(Debug, Default, Clone, Copy, Resource)]
+pub struct FlockMetrics {
+    /// Number of simulation ticks sampled so far.
+    pub tick: u64,
+    /// Length of the mean heading, 1.0 when all boids fly in the same direction.
+    pub polarization: f32,
+    /// Normalized angular momentum around the centroid, 1.0 for a perfect mill.
+    pub milling: f32,
+    /// Mean number of neighbors within the alignment radius.
+    pub density: f32,
+    /// Mean speed of all boids.
+    pub speed: f32,
+    /// Overlaps between boids resolved by the collision pass since the previous tick.
+    pub contacts: usize,
+}
+
+/// Rolling buffer of recent metrics samples, shown by the metrics panel.
+#[derive(Resource)]
+pub struct MetricsHistory {
+    pub samples: VecDeque<FlockMetrics>,
+    pub capacity: usize,
+    pub frozen: bool,
+}
+
+impl Default for MetricsHistory {
+    fn default() -> Self {
+        Self {
+            samples: VecDeque::new(),
+            capacity: 1000,
+            frozen: false,
+        }
+    }
+}
+
+impl MetricsHistory {
+    pub fn push(&mut self, sample: FlockMetrics) {
+        if self.frozen {
+            return;
+        }
+        while self.samples.len() >= self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+}
+
+/// Computes polarization, milling, density and speed of the given boids, in 3D milling
+/// measures the angular momentum around the centroid regardless of the axis the flock
+/// mills around.
+///
+/// Arguments:
+/// boids: the position and velocity of all boids
+/// neighbor_distance: how close other boids are counted as neighbors
+///
+/// Returns: metrics with the tick and contacts left at zero
+pub fn compute_metrics<V: SteeringVector>(
+    boids: &[(V, V)],
+    neighbor_distance: f32,
+) -> FlockMetrics {
+    if boids.is_empty() {
+        return FlockMetrics::default();
+    }
+    let count = boids.len() as f32;
+
+    let centroid = boids
+        .iter()
+        .fold(V::ZERO, |sum, (position, _)| sum + *position)
+        / count;
+
+    let mut heading_sum = V::ZERO;
+    let mut speed_sum = 0.0;
+    let mut angular_momentum = Vec3::ZERO;
+    let mut angular_momentum_norm = 0.0;
+    let mut neighbor_sum = 0;
+    for (position, velocity) in boids {
+        let speed = velocity.length();
+        speed_sum += speed;
+        if speed > 0.0 {
+            heading_sum += *velocity / speed;
+        }
+
+        let offset = *position - centroid;
+        angular_momentum += offset.cross(*velocity);
+        angular_momentum_norm += offset.length() * speed;
+
+        neighbor_sum += count_neighbors(
+            *position,
+            boids.iter().map(|(other_position, _)| *other_position),
+            neighbor_distance,
+        );
+    }
+
+    FlockMetrics {
+        tick: 0,
+        polarization: heading_sum.length() / count,
+        milling: if angular_momentum_norm > 0.0 {
+            angular_momentum.length() / angular_momentum_norm
+        } else {
+            0.0
+        },
+        density: neighbor_sum as f32 / count,
+        speed: speed_sum / count,
+        contacts: 0,
+    }
+}
+
+pub fn update_metrics(
+    timer: Res<BoidTimer>,
+    settings: Res<BoidSettings>,
+    mut metrics: ResMut<FlockMetrics>,
+    mut history: ResMut<MetricsHistory>,
+    mut contacts: ResMut<Contacts>,
+    query: Query<(&Position, &Velocity), With<Boid>>,
+) {
+    if !timer.finished() {
+        return;
+    }
+
+    let boids: Vec<(Vec2, Vec2)> = query
+        .iter()
+        .map(|(position, velocity)| (position.0, velocity.0))
+        .collect();
+
+    let tick = metrics.tick + 1;
+    *metrics = FlockMetrics {
+        tick,
+        contacts: std::mem::take(&mut contacts.0),
+        ..compute_metrics(&boids, settings.alignment_radius)
+    };
+    history.push(*metrics);
+}
+
+pub fn update_metrics_3d(
+    timer: Res<BoidTimer>,
+    settings: Res<BoidSettings>,
+    mut metrics: ResMut<FlockMetrics>,
+    mut history: ResMut<MetricsHistory>,
+    query: Query<(&Position3d, &Velocity3d), With<Boid3d>>,
+) {
+    if !timer.finished() {
+        return;
+    }
+
+    let boids: Vec<(Vec3, Vec3)> = query
+        .iter()
+        .map(|(position, velocity)| (position.0, velocity.0))
+        .collect();
+
+    let tick = metrics.tick + 1;
+    *metrics = FlockMetrics {
+        tick,
+        ..compute_metrics(&boids, settings.alignment_radius)
+    };
+    history.push(*metrics);
+}
@@ -0,0 +1,71 @@
+use bevy::prelude::Vec2;
+
+/// Pseudo random value between -1.0 and 1.0 for a lattice point.
+fn hash(seed: u32, x: i32, y: i32) -> f32 {
+    let mut hash = seed
+        .wrapping_mul(0x27d4_eb2d)
+        .wrapping_add((x as u32).wrapping_mul(0x8da6_b343))
+        .wrapping_add((y as u32).wrapping_mul(0xd816_3841));
+    hash ^= hash >> 15;
+    hash = hash.wrapping_mul(0x2c1b_3c6d);
+    hash ^= hash >> 12;
+    hash = hash.wrapping_mul(0x297a_2d39);
+    hash ^= hash >> 15;
+    hash as f32 / u32::MAX as f32 * 2.0 - 1.0
+}
+
+/// Seeded value noise between -1.0 and 1.0 that varies smoothly over one unit.
+pub fn value_noise(seed: u32, position: Vec2) -> f32 {
+    let cell = position.floor();
+    let (x, y) = (cell.x as i32, cell.y as i32);
+    let t = position - cell;
+    // smoothstep, so the gradient is continuous across cells
+    let t = t * t * (Vec2::splat(3.0) - 2.0 * t);
+
+    let bottom = hash(seed, x, y) + (hash(seed, x + 1, y) - hash(seed, x, y)) * t.x;
+    let top = hash(seed, x, y + 1) + (hash(seed, x + 1, y + 1) - hash(seed, x, y + 1)) * t.x;
+    bottom + (top - bottom) * t.y
+}
+
+/// Divergence free vector field from the curl of value noise, so particles following
+/// it swirl around instead of gathering in sinks.
+pub fn curl_noise(seed: u32, position: Vec2) -> Vec2 {
+    let epsilon = 0.01;
+    let dx = value_noise(seed, position + Vec2::X * epsilon)
+        - value_noise(seed, position - Vec2::X * epsilon);
+    let dy = value_noise(seed, position + Vec2::Y * epsilon)
+        - value_noise(seed, position - Vec2::Y * epsilon);
+    Vec2::new(dy, -dx) / (2.0 * epsilon)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn value_noise_is_seeded_and_bounded() {
+        let position = Vec2::new(3.7, -1.2);
+        assert_eq!(value_noise(1, position), value_noise(1, position));
+        assert_ne!(value_noise(1, position), value_noise(2, position));
+        for i in 0..100 {
+            let value = value_noise(5, Vec2::new(i as f32 * 0.37, i as f32 * -0.21));
+            assert!((-1.0..=1.0).contains(&value));
+        }
+    }
+
+    #[test]
+    fn curl_noise_is_divergence_free() {
+        // central differences of the curl, its divergence should vanish
+        let epsilon = 0.05;
+        for i in 0..20 {
+            let position = Vec2::new(i as f32 * 0.31 + 0.1, i as f32 * 0.17 + 0.2);
+            let ddx = curl_noise(3, position + Vec2::X * epsilon).x
+                - curl_noise(3, position - Vec2::X * epsilon).x;
+            let ddy = curl_noise(3, position + Vec2::Y * epsilon).y
+                - curl_noise(3, position - Vec2::Y * epsilon).y;
+            let divergence = (ddx + ddy) / (2.0 * epsilon);
+            let scale = curl_noise(3, position).length().max(1.0);
+            assert!(divergence.abs() < 0.1 * scale, "divergence {}", divergence);
+        }
+    }
+}
@@ -0,0 +1,180 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::{Component, Resource};
+use rand::Rng;
+
+use crate::boids::BoidSettings;
+use crate::physics::PhysicsSettings;
+
+/// Smallest value drawn, a parameter of zero would freeze or blind a boid.
+const MIN_VALUE: f32 = 1e-3;
+
+/// How a per boid parameter is drawn when spawning.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Distribution {
+    Constant(f32),
+    Uniform { min: f32, max: f32 },
+    Normal { mean: f32, std_dev: f32 },
+}
+
+impl Distribution {
+    pub const SHAPES: [&'static str; 3] = ["Constant", "Uniform", "Normal"];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Distribution::Constant(_) => "Constant",
+            Distribution::Uniform { .. } => "Uniform",
+            Distribution::Normal { .. } => "Normal",
+        }
+    }
+
+    pub fn mean(&self) -> f32 {
+        match *self {
+            Distribution::Constant(value) => value,
+            Distribution::Uniform { min, max } => (min + max) / 2.0,
+            Distribution::Normal { mean, .. } => mean,
+        }
+    }
+
+    /// Same mean with the given shape, uniform and normal start without spread.
+    pub fn with_shape(&self, shape: &str) -> Self {
+        let mean = self.mean();
+        match shape {
+            "Uniform" => Distribution::Uniform {
+                min: mean,
+                max: mean,
+            },
+            "Normal" => Distribution::Normal { mean, std_dev: 0.0 },
+            _ => Distribution::Constant(mean),
+        }
+    }
+
+    /// Draws a positive value. Normal draws below the minimum are drawn again, so the
+    /// tail does not pile up at the minimum, other values are clamped to it.
+    pub fn sample(&self, rng: &mut impl Rng) -> f32 {
+        let value = match *self {
+            Distribution::Constant(value) => value,
+            Distribution::Uniform { min, max } if min < max => rng.gen_range(min..max),
+            Distribution::Uniform { min, .. } => min,
+            Distribution::Normal { mean, std_dev } => {
+                let mut value = mean;
+                for _ in 0..16 {
+                    // Box-Muller transform, 1.0 - u keeps the logarithm finite
+                    let u: f32 = rng.gen();
+                    let v: f32 = rng.gen();
+                    value = mean + std_dev * (-2.0 * (1.0 - u).ln()).sqrt() * (2.0 * PI * v).cos();
+                    if value >= MIN_VALUE {
+                        break;
+                    }
+                }
+                value
+            }
+        };
+        value.max(MIN_VALUE)
+    }
+}
+
+/// Per boid overrides of the speed, force and neighborhood radii in `BoidSettings`
+/// and the mass in `PhysicsSettings`.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct BoidParams {
+    pub max_speed: f32,
+    pub max_force: f32,
+    pub separation_radius: f32,
+    pub alignment_radius: f32,
+    pub cohesion_radius: f32,
+    pub mass: f32,
+}
+
+impl BoidParams {
+    /// The shared parameters, for boids without overrides.
+    pub fn from_settings(settings: &BoidSettings, physics: &PhysicsSettings) -> Self {
+        Self {
+            max_speed: settings.max_speed,
+            max_force: settings.max_force,
+            separation_radius: settings.separation_radius,
+            alignment_radius: settings.alignment_radius,
+            cohesion_radius: settings.cohesion_radius,
+            mass: physics.mass,
+        }
+    }
+}
+
+/// Distributions `BoidParams` are drawn from when spawning.
+#[derive(Debug, Resource)]
+pub struct BoidParamSettings {
+    /// Give each spawned boid its own parameters, otherwise all use `BoidSettings`.
+    pub enabled: bool,
+    pub max_speed: Distribution,
+    pub max_force: Distribution,
+    pub separation_radius: Distribution,
+    pub alignment_radius: Distribution,
+    pub cohesion_radius: Distribution,
+    pub mass: Distribution,
+}
+
+impl BoidParamSettings {
+    /// Command line names of the parameters.
+    pub const NAMES: [&'static str; 6] = [
+        "max-speed",
+        "max-force",
+        "separation-radius",
+        "alignment-radius",
+        "cohesion-radius",
+        "mass",
+    ];
+
+    pub fn from_settings(settings: &BoidSettings, physics: &PhysicsSettings) -> Self {
+        Self {
+            enabled: false,
+            max_speed: Distribution::Constant(settings.max_speed),
+            max_force: Distribution::Constant(settings.max_force),
+            separation_radius: Distribution::Constant(settings.separation_radius),
+            alignment_radius: Distribution::Constant(settings.alignment_radius),
+            cohesion_radius: Distribution::Constant(settings.cohesion_radius),
+            mass: Distribution::Constant(physics.mass),
+        }
+    }
+
+    /// Sets the constant distributions to the current shared values, so enabling per boid
+    /// parameters keeps the slider tuning done since startup.
+    pub fn seed_constants(&mut self, settings: &BoidSettings, physics: &PhysicsSettings) {
+        let shared = BoidParams::from_settings(settings, physics);
+        for (distribution, value) in [
+            (&mut self.max_speed, shared.max_speed),
+            (&mut self.max_force, shared.max_force),
+            (&mut self.separation_radius, shared.separation_radius),
+            (&mut self.alignment_radius, shared.alignment_radius),
+            (&mut self.cohesion_radius, shared.cohesion_radius),
+            (&mut self.mass, shared.mass),
+        ] {
+            if let Distribution::Constant(constant) = distribution {
+                *constant = value;
+            }
+        }
+    }
+
+    /// Distribution of a parameter by its command line name, like `max-speed`.
+    pub fn distribution_mut(&mut self, name: &str) -> Option<&mut Distribution> {
+        match name {
+            "max-speed" => Some(&mut self.max_speed),
+            "max-force" => Some(&mut self.max_force),
+            "separation-radius" => Some(&mut self.separation_radius),
+            "alignment-radius" => Some(&mut self.alignment_radius),
+            "cohesion-radius" => Some(&mut self.cohesion_radius),
+            "mass" => Some(&mut self.mass),
+            _ => None,
+        }
+    }
+
+    pub fn sample(&self, rng: &mut impl Rng) -> BoidParams {
+        BoidParams {
+            max_speed: self.max_speed.sample(rng),
+            max_force: self.max_force.sample(rng),
+            separation_radius: self.separation_radius.sample(rng),
+            alignment_radius: self.alignment_radius.sample(rng),
+            cohesion_radius: self.cohesion_radius.sample(rng),
+            mass: self.mass.sample(rng),
+        }
+    }
+}
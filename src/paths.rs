@@ -0,0 +1,205 @@
+use bevy::{
+    prelude::{
+        Camera, Commands, Component, Entity, GlobalTransform, Input, KeyCode, MouseButton,
+        OrthographicProjection, Query, Res, ResMut, Resource, Vec2, With,
+    },
+    window::{PrimaryWindow, Window},
+};
+use bevy_egui::EguiContexts;
+
+use crate::render::{get_cursor_world_position, MainCamera2d};
+
+/// Clicks closer than this many pixels to a control point grab or delete it.
+const PICK_DISTANCE: f32 = 20.0;
+
+/// Line segments each spline segment is split into.
+const SPLINE_SUBDIVISIONS: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathShape {
+    /// Straight lines between the control points.
+    Polyline,
+    /// A Catmull-Rom spline through the control points.
+    CatmullRom,
+}
+
+impl PathShape {
+    pub const ALL: [PathShape; 2] = [PathShape::Polyline, PathShape::CatmullRom];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PathShape::Polyline => "Polyline",
+            PathShape::CatmullRom => "Catmull-Rom Spline",
+        }
+    }
+}
+
+/// A corridor boids steer to stay inside of while moving along it, in the order
+/// of its control points.
+#[derive(Debug, Clone, Component)]
+pub struct Path {
+    pub control_points: Vec<Vec2>,
+    pub shape: PathShape,
+    pub width: f32,
+    /// Connect the last control point back to the first.
+    pub closed: bool,
+}
+
+impl Default for Path {
+    fn default() -> Self {
+        Self {
+            control_points: Vec::new(),
+            shape: PathShape::CatmullRom,
+            width: 40.0,
+            closed: false,
+        }
+    }
+}
+
+fn catmull_rom(p0: Vec2, p1: Vec2, p2: Vec2, p3: Vec2, t: f32) -> Vec2 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    0.5 * (2.0 * p1
+        + (p2 - p0) * t
+        + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2
+        + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t3)
+}
+
+impl Path {
+    /// Points of the center line, splines are approximated with short segments.
+    /// Closed paths repeat the first point at the end.
+    pub fn points(&self) -> Vec<Vec2> {
+        let control_points = &self.control_points;
+        let count = control_points.len();
+        if count < 2 {
+            return control_points.clone();
+        }
+        let mut points = match self.shape {
+            PathShape::Polyline => control_points.clone(),
+            PathShape::CatmullRom => {
+                // open paths repeat their end points as the outer spline points
+                let point = |index: isize| {
+                    if self.closed {
+                        control_points[index.rem_euclid(count as isize) as usize]
+                    } else {
+                        control_points[index.clamp(0, count as isize - 1) as usize]
+                    }
+                };
+                let segments = if self.closed { count } else { count - 1 };
+                let mut points = Vec::with_capacity(segments * SPLINE_SUBDIVISIONS + 1);
+                for segment in 0..segments as isize {
+                    for step in 0..SPLINE_SUBDIVISIONS {
+                        points.push(catmull_rom(
+                            point(segment - 1),
+                            point(segment),
+                            point(segment + 1),
+                            point(segment + 2),
+                            step as f32 / SPLINE_SUBDIVISIONS as f32,
+                        ));
+                    }
+                }
+                if !self.closed {
+                    points.push(control_points[count - 1]);
+                }
+                points
+            }
+        };
+        if self.closed {
+            points.push(points[0]);
+        }
+        points
+    }
+}
+
+/// The path new control points are added to and the control point being dragged.
+#[derive(Debug, Default, Resource)]
+pub struct PathEditing {
+    pub active: Option<Entity>,
+    pub dragging: Option<(Entity, usize)>,
+}
+
+fn find_control_point(
+    paths: &Query<(Entity, &mut Path)>,
+    cursor: Vec2,
+    distance: f32,
+) -> Option<(Entity, usize)> {
+    paths
+        .iter()
+        .flat_map(|(entity, path)| {
+            path.control_points
+                .iter()
+                .enumerate()
+                .map(move |(index, point)| ((entity, index), point.distance(cursor)))
+        })
+        .filter(|(_, point_distance)| *point_distance < distance)
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(point, _)| point)
+}
+
+/// With control held, middle click appends a control point to the active path or grabs
+/// the one under the cursor, dragging moves it, right click deletes it.
+#[allow(clippy::too_many_arguments)]
+pub fn update_paths_from_mouse(
+    mut commands: Commands,
+    buttons: Res<Input<MouseButton>>,
+    keys: Res<Input<KeyCode>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera_transform: Query<
+        (&Camera, &GlobalTransform, &OrthographicProjection),
+        With<MainCamera2d>,
+    >,
+    mut contexts: EguiContexts,
+    mut editing: ResMut<PathEditing>,
+    mut paths: Query<(Entity, &mut Path)>,
+) {
+    if buttons.just_released(MouseButton::Middle) {
+        editing.dragging = None;
+    }
+    let (camera, camera_transform, projection) = camera_transform.single();
+    let window = windows.get_single().unwrap();
+    let Some(cursor) = get_cursor_world_position(window, camera, camera_transform) else {
+        return;
+    };
+
+    if let Some((entity, index)) = editing.dragging {
+        if let Ok((_, mut path)) = paths.get_mut(entity) {
+            if path
+                .control_points
+                .get(index)
+                .is_some_and(|point| *point != cursor)
+            {
+                path.control_points[index] = cursor;
+            }
+        }
+    }
+
+    if !keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
+        || contexts.ctx_mut().wants_pointer_input()
+    {
+        return;
+    }
+    let pick_distance = PICK_DISTANCE * projection.scale;
+    if buttons.just_pressed(MouseButton::Middle) {
+        if let Some(point) = find_control_point(&paths, cursor, pick_distance) {
+            editing.dragging = Some(point);
+            editing.active = Some(point.0);
+        } else if let Some(Ok((_, mut path))) = editing.active.map(|entity| paths.get_mut(entity)) {
+            path.control_points.push(cursor);
+        } else {
+            let path = Path {
+                control_points: vec![cursor],
+                ..Default::default()
+            };
+            editing.active = Some(commands.spawn(path).id());
+        }
+    } else if buttons.just_pressed(MouseButton::Right) {
+        if let Some((entity, index)) = find_control_point(&paths, cursor, pick_distance) {
+            if let Ok((_, mut path)) = paths.get_mut(entity) {
+                path.control_points.remove(index);
+                if path.control_points.is_empty() {
+                    commands.entity(entity).despawn();
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use bevy::prelude::{Resource, Vec2};
+
+/// Optional flight model. While it is off, the limited steering force changes the
+/// velocity directly.
+#[derive(Debug, Resource)]
+pub struct PhysicsSettings {
+    pub enabled: bool,
+    /// Mass of boids without own parameters, forces accelerate heavier boids less.
+    pub mass: f32,
+    /// Drag proportional to the speed.
+    pub linear_drag: f32,
+    /// Drag proportional to the squared speed, like air resistance.
+    pub quadratic_drag: f32,
+    /// Stall speed, boids never fly slower than this unless their max speed is lower.
+    pub min_speed: f32,
+}
+
+impl Default for PhysicsSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            mass: 1.0,
+            linear_drag: 0.0,
+            quadratic_drag: 0.0,
+            min_speed: 0.0,
+        }
+    }
+}
+
+impl PhysicsSettings {
+    /// Change of velocity from drag during one tick, never more than stops the boid.
+    pub fn drag(&self, velocity: Vec2, mass: f32) -> Vec2 {
+        let speed = velocity.length();
+        let drag = -velocity * (self.linear_drag + self.quadratic_drag * speed) / mass;
+        drag.clamp_length_max(speed)
+    }
+}
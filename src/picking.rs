@@ -0,0 +1,58 @@
+use bevy::{
+    prelude::{
+        Camera, Color, Entity, Gizmos, GlobalTransform, Input, MouseButton, OrthographicProjection,
+        Query, Res, ResMut, Resource, With,
+    },
+    window::{PrimaryWindow, Window},
+};
+
+use crate::boids::{Boid, BoidSettings, Position};
+use crate::camera::MouseDrag;
+use crate::render::{get_cursor_world_position, MainCamera2d};
+
+/// Clicks further than this many pixels from any boid clear the selection.
+const PICK_DISTANCE: f32 = 50.0;
+
+/// The boid picked with the left mouse button.
+#[derive(Debug, Default, Resource)]
+pub struct SelectedBoid(pub Option<Entity>);
+
+pub fn select_boid_from_mouse_click(
+    buttons: Res<Input<MouseButton>>,
+    drag: Res<MouseDrag>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera_transform: Query<
+        (&Camera, &GlobalTransform, &OrthographicProjection),
+        With<MainCamera2d>,
+    >,
+    mut selected: ResMut<SelectedBoid>,
+    boids: Query<(Entity, &Position), With<Boid>>,
+) {
+    // a release after a press outside of egui that did not pan the camera
+    if !buttons.just_released(MouseButton::Left) || drag.press_cursor.is_none() || drag.moved {
+        return;
+    }
+    let (camera, camera_transform, projection) = camera_transform.single();
+    let window = windows.get_single().unwrap();
+    let Some(cursor) = get_cursor_world_position(window, camera, camera_transform) else {
+        return;
+    };
+
+    selected.0 = boids
+        .iter()
+        .map(|(entity, position)| (entity, position.0.distance(cursor)))
+        .filter(|(_, distance)| *distance < PICK_DISTANCE * projection.scale)
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(entity, _)| entity);
+}
+
+pub fn draw_selected_boid(
+    selected: Res<SelectedBoid>,
+    settings: Res<BoidSettings>,
+    mut gizmos: Gizmos,
+    boids: Query<&Position, With<Boid>>,
+) {
+    if let Some(Ok(position)) = selected.0.map(|entity| boids.get(entity)) {
+        gizmos.circle_2d(position.0, settings.boid_radius * 3.0, Color::ORANGE_RED);
+    }
+}
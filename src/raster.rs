@@ -0,0 +1,136 @@
+use bevy::prelude::{error, info, Color, Res, ResMut, Resource, Vec2};
+use tiny_skia::{Paint, PathBuilder, Pixmap, Stroke, Transform};
+
+use crate::boids::BoidTimer;
+use crate::metrics::FlockMetrics;
+use crate::scene::{Scene, SceneQuery};
+
+/// Rasterizes the scene on the CPU and writes PNG files every few ticks,
+/// for machines without a GPU.
+#[derive(Debug, Resource)]
+pub struct PngExport {
+    /// File name, `{tick}` is replaced with the current tick.
+    pub path: String,
+    /// Write a file every this many ticks, 0 disables periodic export.
+    pub every: u64,
+    /// Width of the image in pixels, the height follows the arena aspect ratio.
+    pub width: u32,
+    /// Write a file on the next frame, set by the UI.
+    pub requested: bool,
+}
+
+impl Default for PngExport {
+    fn default() -> Self {
+        Self {
+            path: "frame_{tick}.png".into(),
+            every: 0,
+            width: 1024,
+            requested: false,
+        }
+    }
+}
+
+fn to_skia_color(color: Color) -> tiny_skia::Color {
+    let [r, g, b, a] = color.as_rgba_f32();
+    tiny_skia::Color::from_rgba(r, g, b, a).unwrap_or(tiny_skia::Color::BLACK)
+}
+
+fn stroke_path(
+    pixmap: &mut Pixmap,
+    transform: Transform,
+    path: PathBuilder,
+    color: Color,
+    width: f32,
+) {
+    let Some(path) = path.finish() else {
+        return;
+    };
+    let mut paint = Paint::default();
+    paint.set_color(to_skia_color(color));
+    paint.anti_alias = true;
+    let stroke = Stroke {
+        width,
+        ..Default::default()
+    };
+    pixmap.stroke_path(&path, &paint, &stroke, transform, None);
+}
+
+/// Draws the scene like the window does, as a white image of the given width.
+pub fn rasterize(scene: &Scene, width: u32) -> Option<Pixmap> {
+    let min = scene.min();
+    let size = scene.max() - min;
+    let scale = width as f32 / size.x;
+    let height = (size.y * scale).round() as u32;
+    let mut pixmap = Pixmap::new(width, height)?;
+    pixmap.fill(tiny_skia::Color::WHITE);
+
+    // world units to pixels, with the y axis pointing down
+    let transform = Transform::from_row(
+        scale,
+        0.0,
+        0.0,
+        -scale,
+        -min.x * scale,
+        scene.max().y * scale,
+    );
+
+    // walls
+    let mut walls = PathBuilder::new();
+    walls.move_to(scene.boundary_min.x, scene.boundary_min.y);
+    walls.line_to(scene.boundary_min.x, scene.boundary_max.y);
+    walls.line_to(scene.boundary_max.x, scene.boundary_max.y);
+    walls.line_to(scene.boundary_max.x, scene.boundary_min.y);
+    walls.close();
+    stroke_path(&mut pixmap, transform, walls, Color::BLUE, 1.0);
+
+    // targets, a circle with a cross
+    for target in scene.targets.iter() {
+        let radius = Scene::TARGET_RADIUS;
+        let center = target.position;
+        let mut path = PathBuilder::new();
+        path.push_circle(center.x, center.y, radius);
+        path.move_to(center.x - radius, center.y - radius);
+        path.line_to(center.x + radius, center.y + radius);
+        path.move_to(center.x - radius, center.y + radius);
+        path.line_to(center.x + radius, center.y - radius);
+        stroke_path(&mut pixmap, transform, path, target.color(), 1.0);
+    }
+
+    // boids, a circle and a heading line
+    for boid in scene.boids.iter() {
+        let heading: Vec2 = boid.position + boid.heading * scene.boid_radius;
+        let mut path = PathBuilder::new();
+        path.push_circle(boid.position.x, boid.position.y, scene.boid_radius);
+        path.move_to(boid.position.x, boid.position.y);
+        path.line_to(heading.x, heading.y);
+        stroke_path(&mut pixmap, transform, path, boid.color, 2.0);
+    }
+
+    Some(pixmap)
+}
+
+pub fn export_png(
+    timer: Res<BoidTimer>,
+    metrics: Res<FlockMetrics>,
+    mut export: ResMut<PngExport>,
+    scene: SceneQuery,
+) {
+    let periodic =
+        export.every > 0 && timer.finished() && metrics.tick.is_multiple_of(export.every);
+    if !export.requested && !periodic {
+        return;
+    }
+    export.requested = false;
+
+    let path = export
+        .path
+        .replace("{tick}", &format!("{:06}", metrics.tick));
+    let Some(pixmap) = rasterize(&scene.scene(), export.width) else {
+        error!("could not create a {} pixel wide image", export.width);
+        return;
+    };
+    match pixmap.save_png(&path) {
+        Ok(()) => info!("wrote {}", path),
+        Err(err) => error!("could not write {}: {}", path, err),
+    }
+}
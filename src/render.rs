@@ -1,23 +1,156 @@
 use std::f32::consts::PI;
 
-use bevy::prelude::{
-    Added, Camera2dBundle, Changed, Color, Commands, Component, Entity, Quat, Query, Res,
-    Transform, Vec2, Vec3, Visibility, With,
+use bevy::{
+    prelude::{
+        Added, Assets, Camera, Camera2dBundle, Changed, Color, ColorMaterial, Commands, Component,
+        DetectChanges, Entity, Gizmos, GlobalTransform, Handle, Image, Local, Mesh, Quat, Query,
+        Res, ResMut, Resource, Transform, Vec2, Vec3, Visibility, With, Without,
+    },
+    render::{
+        render_resource::{Extent3d, PrimitiveTopology, TextureDimension, TextureFormat},
+        texture::ImageSampler,
+        view::NoFrustumCulling,
+    },
+    sprite::{MaterialMesh2dBundle, Mesh2dHandle, Sprite, SpriteBundle},
+    window::Window,
 };
 use bevy_prototype_lyon::{
     prelude::{GeometryBuilder, ShapeBundle, Stroke},
     shapes,
 };
 
-use crate::boids::{Boid, BoidSettings, Position, TargetPosition, Velocity, ViewRadius};
+use crate::boids::{Boid, BoidSettings, BoidTimer, Position, Velocity};
+use crate::clusters::{ClusterId, FlockId, Neighborhood};
+use crate::colormap::Colormap;
+use crate::debug::draw_arrow;
+use crate::flowfield::FlowField;
+use crate::heatmap::Heatmap;
+use crate::leaders::Leader;
+use crate::paths::{Path, PathEditing};
+use crate::routes::{Route, RouteMode, RouteTarget};
+use crate::scene::Scene;
+use crate::targets::Target;
+use crate::trails::{Trail, TrailSettings};
 
 #[derive(Component)]
 pub struct MainCamera2d;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoidColorMode {
+    /// All boids are drawn black.
+    Uniform,
+    Speed,
+    Heading,
+    /// Boids within the cohesion radius per area.
+    Density,
+    /// Boids within the alignment radius.
+    NeighborCount,
+    /// Flock identity that persists across ticks.
+    FlockId,
+    /// Cluster index of the last tick.
+    ClusterId,
+}
+
+impl BoidColorMode {
+    pub const ALL: [BoidColorMode; 7] = [
+        BoidColorMode::Uniform,
+        BoidColorMode::Speed,
+        BoidColorMode::Heading,
+        BoidColorMode::Density,
+        BoidColorMode::NeighborCount,
+        BoidColorMode::FlockId,
+        BoidColorMode::ClusterId,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BoidColorMode::Uniform => "Uniform",
+            BoidColorMode::Speed => "Speed",
+            BoidColorMode::Heading => "Heading (degrees)",
+            BoidColorMode::Density => "Local Density (per 10000 px²)",
+            BoidColorMode::NeighborCount => "Neighbor Count",
+            BoidColorMode::FlockId => "Flock Id",
+            BoidColorMode::ClusterId => "Cluster Id",
+        }
+    }
+
+    /// Whether the mode maps discrete ids instead of a continuous value.
+    pub fn is_categorical(&self) -> bool {
+        matches!(self, BoidColorMode::FlockId | BoidColorMode::ClusterId)
+    }
+}
+
+/// Value range of the current color mode, written by the color system for the legend.
+#[derive(Debug, Default, Resource)]
+pub struct ColorLegend {
+    pub min: f32,
+    pub max: f32,
+}
+
+#[derive(Component)]
+pub struct HeatmapRenderable;
+
+/// Single mesh holding the shapes of all boids, used by the batched backend.
 #[derive(Component)]
-pub struct TargetPositionRenderable;
+pub struct BatchedBoidsRenderable;
+
+/// Boid velocity of the last two simulation ticks, rendering turns the heading between them.
+/// The position half of the interpolation is covered by `apply_boid_velocity`, which moves
+/// `Position` every frame, so boids are drawn at their live position. Blending two tick
+/// snapshots on top of that would only add a tick of lag and offset the boid from the
+/// selection ring, trails and picking, which all use `Position`.
+#[derive(Debug, Clone, Component)]
+pub struct InterpolationState {
+    pub previous_velocity: Vec2,
+    pub current_velocity: Vec2,
+}
+
+/// Color a boid is drawn with, shared by all render backends.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct BoidColor(pub Color);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderBackend {
+    /// One tessellated lyon shape per boid.
+    Lyon,
+    /// One mesh for all boids, rebuilt every frame.
+    Batched,
+}
+
+#[derive(Resource)]
+pub struct RenderSettings {
+    pub color_mode: BoidColorMode,
+    pub colormap: Colormap,
+    pub backend: RenderBackend,
+    /// Turn boids between their headings of the last two ticks instead of snapping to the
+    /// latest one, this smooths low tick rates at the cost of one tick of heading latency.
+    /// Positions are smooth either way, see `InterpolationState`.
+    pub interpolate: bool,
+    pub show_heatmap: bool,
+    pub show_flow_field: bool,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        Self {
+            color_mode: BoidColorMode::Uniform,
+            colormap: Colormap::Viridis,
+            backend: RenderBackend::Lyon,
+            interpolate: true,
+            show_heatmap: false,
+            show_flow_field: false,
+        }
+    }
+}
 
-pub fn setup_render(mut commands: Commands, settings: Res<BoidSettings>) {
+pub fn setup_render(
+    mut commands: Commands,
+    settings: Res<BoidSettings>,
+    heatmap: Res<Heatmap>,
+    mut images: ResMut<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
     let mut builder = GeometryBuilder::new();
 
     let steps = 100;
@@ -46,29 +179,47 @@ pub fn setup_render(mut commands: Commands, settings: Res<BoidSettings>) {
         Stroke::new(Color::hex("999999").unwrap(), 1.0),
     ));
 
-    let mut builder = GeometryBuilder::new();
-
-    let target_radius = 10.0;
-    builder = builder.add(&shapes::Circle {
-        radius: target_radius,
-        center: Vec2::ZERO,
-    });
-    builder = builder.add(&shapes::Line(
-        Vec2::new(-target_radius, -target_radius),
-        Vec2::new(target_radius, target_radius),
-    ));
-    builder = builder.add(&shapes::Line(
-        Vec2::new(-target_radius, target_radius),
-        Vec2::new(target_radius, -target_radius),
+    // heatmap overlay, between the grid and the boids
+    let mut image = Image::new_fill(
+        Extent3d {
+            width: heatmap.width as u32,
+            height: heatmap.height as u32,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 0],
+        TextureFormat::Rgba8UnormSrgb,
+    );
+    image.sampler_descriptor = ImageSampler::nearest();
+    let center = heatmap.min + heatmap.size() / 2.0;
+    commands.spawn((
+        SpriteBundle {
+            texture: images.add(image),
+            sprite: Sprite {
+                custom_size: Some(heatmap.size()),
+                ..Default::default()
+            },
+            transform: Transform::from_translation(Vec3::new(center.x, center.y, 0.5)),
+            visibility: Visibility::Hidden,
+            ..Default::default()
+        },
+        HeatmapRenderable,
     ));
+
+    // all boids in one mesh for the batched backend, the vertices are rewritten every frame
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, Vec::<[f32; 3]>::new());
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, Vec::<[f32; 4]>::new());
     commands.spawn((
-        ShapeBundle {
-            path: builder.build(),
-            transform: Transform::from_translation(Vec3::new(0.0, 0.0, 2.0)),
+        MaterialMesh2dBundle {
+            mesh: meshes.add(mesh).into(),
+            material: materials.add(ColorMaterial::from(Color::WHITE)),
+            visibility: Visibility::Hidden,
             ..Default::default()
         },
-        Stroke::new(Color::RED, 1.0),
-        TargetPositionRenderable,
+        // the bounds change every frame, skip culling instead of recomputing them
+        NoFrustumCulling,
+        BatchedBoidsRenderable,
     ));
 
     // walls
@@ -98,6 +249,27 @@ pub fn setup_camera(mut commands: Commands) {
     commands.spawn((Camera2dBundle::default(), MainCamera2d));
 }
 
+/// Returns the world position under the mouse cursor, if it is inside the window.
+pub fn get_cursor_world_position(
+    window: &Window,
+    camera: &Camera,
+    camera_transform: &GlobalTransform,
+) -> Option<Vec2> {
+    window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world(camera_transform, cursor))
+        .map(|ray| ray.origin.truncate())
+}
+
+const BOID_STROKE_WIDTH: f32 = 2.0;
+
+fn get_lyon_visibility(backend: RenderBackend) -> Visibility {
+    match backend {
+        RenderBackend::Lyon => Visibility::Inherited,
+        RenderBackend::Batched => Visibility::Hidden,
+    }
+}
+
 fn get_transform_for_boid(position: &Position, velocity: &Velocity) -> Transform {
     let Position(position) = position;
     let Velocity(velocity) = velocity;
@@ -110,62 +282,559 @@ fn get_transform_for_boid(position: &Position, velocity: &Velocity) -> Transform
     )
 }
 
+/// Velocity at the given fraction between the previous and the current tick.
+fn interpolate_velocity(state: &InterpolationState, alpha: f32) -> Velocity {
+    // rotate the heading along the shorter arc and blend the speed separately
+    let previous_angle = state.previous_velocity.y.atan2(state.previous_velocity.x);
+    let current_angle = state.current_velocity.y.atan2(state.current_velocity.x);
+    let delta = (current_angle - previous_angle + PI).rem_euclid(PI * 2.0) - PI;
+    let speed = state.previous_velocity.length()
+        + (state.current_velocity.length() - state.previous_velocity.length()) * alpha;
+    let velocity = Vec2::from_angle(previous_angle + delta * alpha) * speed;
+
+    Velocity(velocity)
+}
+
+/// Transform a boid is drawn with, at its position and with the heading interpolated
+/// between ticks if enabled. Overlays and picking use the same position.
+fn get_render_transform_for_boid(
+    position: &Position,
+    velocity: &Velocity,
+    state: Option<&InterpolationState>,
+    alpha: Option<f32>,
+) -> Transform {
+    match (state, alpha) {
+        (Some(state), Some(alpha)) => {
+            get_transform_for_boid(position, &interpolate_velocity(state, alpha))
+        }
+        _ => get_transform_for_boid(position, velocity),
+    }
+}
+
+fn get_interpolation_alpha(render_settings: &RenderSettings, timer: &BoidTimer) -> Option<f32> {
+    render_settings.interpolate.then(|| timer.progress())
+}
+
+pub fn record_interpolation_state(
+    mut commands: Commands,
+    timer: Res<BoidTimer>,
+    mut boids: Query<(Entity, &Velocity, Option<&mut InterpolationState>), With<Boid>>,
+) {
+    if !timer.finished() {
+        return;
+    }
+    for (entity, velocity, state) in boids.iter_mut() {
+        match state {
+            Some(mut state) => {
+                state.previous_velocity = state.current_velocity;
+                state.current_velocity = velocity.0;
+            }
+            None => {
+                commands.entity(entity).insert(InterpolationState {
+                    previous_velocity: velocity.0,
+                    current_velocity: velocity.0,
+                });
+            }
+        }
+    }
+}
+
+/// Tessellated lyon shape of a boid, a circle with a heading line.
+fn get_boid_shape(
+    boid_radius: f32,
+    position: &Position,
+    velocity: &Velocity,
+    color: Color,
+) -> (ShapeBundle, Stroke) {
+    let mut builder = GeometryBuilder::new();
+
+    // circle representing the boid
+    let circle = shapes::Circle {
+        radius: boid_radius,
+        center: Vec2::ZERO,
+    };
+    let line = shapes::Line(Vec2::ZERO, Vec2::new(0.0, boid_radius));
+
+    builder = builder.add(&circle);
+    builder = builder.add(&line);
+
+    (
+        ShapeBundle {
+            path: builder.build(),
+            transform: get_transform_for_boid(position, velocity),
+            ..Default::default()
+        },
+        Stroke::new(color, BOID_STROKE_WIDTH),
+    )
+}
+
+/// Gives new boids a color, and a lyon shape only with the lyon backend. The batched
+/// backend draws from the color alone, `update_render_backend` adds missing shapes.
+#[allow(clippy::type_complexity)]
 pub fn spawn_boid_renderable(
     settings: Res<BoidSettings>,
+    render_settings: Res<RenderSettings>,
     mut commands: Commands,
-    boids: Query<(Entity, &Position, &Velocity, &ViewRadius), (With<Boid>, Added<Boid>)>,
+    boids: Query<(Entity, &Position, &Velocity), (With<Boid>, Added<Boid>)>,
 ) {
-    for (entity, position, velocity, view_radius) in boids.iter() {
-        let mut builder = GeometryBuilder::new();
-
-        let boid_radius = settings.boid_radius;
+    for (entity, position, velocity) in boids.iter() {
         let boid_color = Color::BLACK;
+        let mut boid = commands.entity(entity);
+        boid.insert(BoidColor(boid_color));
+        if render_settings.backend == RenderBackend::Lyon {
+            boid.insert(get_boid_shape(
+                settings.boid_radius,
+                position,
+                velocity,
+                boid_color,
+            ));
+        }
+    }
+}
 
-        // circle representing the boid
-        let circle = shapes::Circle {
-            radius: boid_radius,
-            center: Vec2::ZERO,
-        };
-        let line = shapes::Line(Vec2::ZERO, Vec2::new(0.0, boid_radius));
+// runs every frame, the interpolated heading changes even when the velocity does not
+#[allow(clippy::type_complexity)]
+pub fn update_boid_renderable_transform(
+    render_settings: Res<RenderSettings>,
+    timer: Res<BoidTimer>,
+    mut boids: Query<
+        (
+            &Position,
+            &Velocity,
+            Option<&InterpolationState>,
+            &mut Transform,
+        ),
+        With<Boid>,
+    >,
+) {
+    if render_settings.backend != RenderBackend::Lyon {
+        return;
+    }
+    let alpha = get_interpolation_alpha(&render_settings, &timer);
+    for (position, velocity, state, mut transform) in boids.iter_mut() {
+        *transform = get_render_transform_for_boid(position, velocity, state, alpha);
+    }
+}
 
-        builder = builder.add(&circle);
-        builder = builder.add(&line);
+pub fn spawn_target_renderable(
+    mut commands: Commands,
+    targets: Query<(Entity, &Target), Added<Target>>,
+) {
+    for (entity, target) in targets.iter() {
+        let mut builder = GeometryBuilder::new();
 
+        // circle with a cross
+        let target_radius = Scene::TARGET_RADIUS;
+        builder = builder.add(&shapes::Circle {
+            radius: target_radius,
+            center: Vec2::ZERO,
+        });
+        builder = builder.add(&shapes::Line(
+            Vec2::new(-target_radius, -target_radius),
+            Vec2::new(target_radius, target_radius),
+        ));
+        builder = builder.add(&shapes::Line(
+            Vec2::new(-target_radius, target_radius),
+            Vec2::new(target_radius, -target_radius),
+        ));
         commands.entity(entity).insert((
             ShapeBundle {
                 path: builder.build(),
-                transform: get_transform_for_boid(position, velocity),
+                transform: Transform::from_translation(target.position.extend(2.0)),
                 ..Default::default()
             },
-            Stroke::new(boid_color, 2.0),
+            Stroke::new(target.color(), 1.0),
         ));
     }
 }
 
-pub fn update_boid_renderable_transform(
+pub fn update_target_renderable(
+    mut targets: Query<(&Target, &mut Transform, &mut Stroke), Changed<Target>>,
+) {
+    for (target, mut transform, mut stroke) in targets.iter_mut() {
+        transform.translation = target.position.extend(2.0);
+        stroke.color = target.color();
+    }
+}
+
+/// Draws the radius of influence of each target, the route target reaches everywhere.
+pub fn draw_target_influence(mut gizmos: Gizmos, targets: Query<&Target, Without<RouteTarget>>) {
+    for target in targets.iter() {
+        gizmos.circle_2d(target.position, target.radius, target.color().with_a(0.3));
+    }
+}
+
+/// Value of a boid for the given color mode, None for the uniform mode.
+fn get_color_value(
+    color_mode: BoidColorMode,
+    velocity: &Velocity,
+    ids: Option<(&ClusterId, &FlockId, &Neighborhood)>,
+) -> Option<f32> {
+    match (color_mode, ids) {
+        (BoidColorMode::Uniform, _) => None,
+        (BoidColorMode::Speed, _) => Some(velocity.0.length()),
+        (BoidColorMode::Heading, _) => Some(
+            velocity
+                .0
+                .y
+                .atan2(velocity.0.x)
+                .to_degrees()
+                .rem_euclid(360.0),
+        ),
+        (BoidColorMode::Density, Some((_, _, neighborhood))) => Some(neighborhood.density),
+        (BoidColorMode::NeighborCount, Some((_, _, neighborhood))) => {
+            Some(neighborhood.neighbors as f32)
+        }
+        (BoidColorMode::FlockId, Some((_, flock_id, _))) => Some(flock_id.0 as f32),
+        (BoidColorMode::ClusterId, Some((cluster_id, _, _))) => Some(cluster_id.0 as f32),
+        _ => None,
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn update_boid_renderable_color(
+    settings: Res<BoidSettings>,
+    render_settings: Res<RenderSettings>,
+    mut legend: ResMut<ColorLegend>,
     mut boids: Query<
-        (Entity, &Position, &Velocity, &ViewRadius, &mut Transform),
-        (With<Boid>, Changed<Position>),
+        (
+            &mut BoidColor,
+            &Velocity,
+            Option<(&ClusterId, &FlockId, &Neighborhood)>,
+        ),
+        With<Boid>,
     >,
 ) {
-    for (entity, position, velocity, view_radius, mut transform) in boids.iter_mut() {
-        *transform = get_transform_for_boid(position, velocity);
+    let color_mode = render_settings.color_mode;
+    let values: Vec<Option<f32>> = boids
+        .iter()
+        .map(|(_, velocity, ids)| get_color_value(color_mode, velocity, ids))
+        .collect();
+
+    // speed and heading have known bounds, the other values are scaled to what is on screen
+    let (min, max) = match color_mode {
+        BoidColorMode::Speed => (0.0, settings.max_speed),
+        BoidColorMode::Heading => (0.0, 360.0),
+        _ => values
+            .iter()
+            .flatten()
+            .fold((f32::MAX, f32::MIN), |(min, max), value| {
+                (min.min(*value), max.max(*value))
+            }),
+    };
+    let (min, max) = if min <= max { (min, max) } else { (0.0, 0.0) };
+    if legend.min != min || legend.max != max {
+        *legend = ColorLegend { min, max };
+    }
+
+    for ((mut boid_color, _, _), value) in boids.iter_mut().zip(values) {
+        let color = match value {
+            None => Color::BLACK,
+            Some(value) if color_mode.is_categorical() => {
+                render_settings.colormap.category(value as usize)
+            }
+            Some(value) if max > min => {
+                render_settings.colormap.sample((value - min) / (max - min))
+            }
+            Some(_) => render_settings.colormap.sample(0.0),
+        };
+        if boid_color.0 != color {
+            boid_color.0 = color;
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn update_boid_renderable_stroke(
+    render_settings: Res<RenderSettings>,
+    mut boids: Query<(&BoidColor, &mut Stroke), (With<Boid>, Changed<BoidColor>)>,
+) {
+    if render_settings.backend != RenderBackend::Lyon {
+        return;
+    }
+    for (boid_color, mut stroke) in boids.iter_mut() {
+        // avoid flagging the stroke as changed, that would tessellate the shape again
+        if stroke.color != boid_color.0 {
+            stroke.color = boid_color.0;
+        }
     }
 }
 
-pub fn update_boid_target_renderable_transform(
-    target_position: Res<TargetPosition>,
-    mut target: Query<(&mut Transform, &mut Visibility), (With<TargetPositionRenderable>)>,
+/// Switches between the lyon shapes and the batched mesh when the backend changes.
+/// Boids spawned while batching get their lyon shape here.
+#[allow(clippy::type_complexity)]
+pub fn update_render_backend(
+    settings: Res<BoidSettings>,
+    render_settings: Res<RenderSettings>,
+    mut current: Local<Option<RenderBackend>>,
+    mut commands: Commands,
+    mut boids: Query<
+        (
+            Entity,
+            &Position,
+            &Velocity,
+            &BoidColor,
+            Option<(&mut Stroke, &mut Transform, &mut Visibility)>,
+        ),
+        (With<Boid>, Without<BatchedBoidsRenderable>),
+    >,
+    mut batched: Query<&mut Visibility, (With<BatchedBoidsRenderable>, Without<Boid>)>,
 ) {
-    if let Ok((mut transform, mut visibility)) = target.get_single_mut() {
-        match target_position.position {
-            Some(position) => {
-                *visibility = Visibility::Visible;
-                transform.translation = Vec3::new(position.x, position.y, 2.0)
+    let backend = render_settings.backend;
+    if *current == Some(backend) {
+        return;
+    }
+    *current = Some(backend);
+
+    if let Ok(mut visibility) = batched.get_single_mut() {
+        *visibility = match backend {
+            RenderBackend::Lyon => Visibility::Hidden,
+            RenderBackend::Batched => Visibility::Inherited,
+        };
+    }
+    let lyon_visibility = get_lyon_visibility(backend);
+    for (entity, position, velocity, boid_color, shape) in boids.iter_mut() {
+        match shape {
+            Some((mut stroke, mut transform, mut visibility)) => {
+                *visibility = lyon_visibility;
+                if backend == RenderBackend::Lyon {
+                    // the shapes were not updated while hidden
+                    *transform = get_transform_for_boid(position, velocity);
+                    stroke.color = boid_color.0;
+                }
             }
-            None => {
-                *visibility = Visibility::Hidden;
+            None if backend == RenderBackend::Lyon => {
+                commands.entity(entity).insert(get_boid_shape(
+                    settings.boid_radius,
+                    position,
+                    velocity,
+                    boid_color.0,
+                ));
             }
+            None => {}
         }
     }
 }
+
+/// Outline of a boid in local space as triangles, matching the lyon circle and heading line.
+fn get_boid_triangles(boid_radius: f32) -> Vec<Vec3> {
+    let segments = 16;
+    let half_width = BOID_STROKE_WIDTH / 2.0;
+    let inner = boid_radius - half_width;
+    let outer = boid_radius + half_width;
+    let mut triangles = Vec::with_capacity(segments * 6 + 6);
+    for segment in 0..segments {
+        let angle_a = segment as f32 / segments as f32 * PI * 2.0;
+        let angle_b = (segment + 1) as f32 / segments as f32 * PI * 2.0;
+        let (a, b) = (Vec2::from_angle(angle_a), Vec2::from_angle(angle_b));
+        triangles.extend(
+            [
+                a * inner,
+                a * outer,
+                b * outer,
+                a * inner,
+                b * outer,
+                b * inner,
+            ]
+            .map(|point| point.extend(0.0)),
+        );
+    }
+    // heading line from the center to the top of the circle
+    let (left, right) = (-half_width, half_width);
+    triangles.extend(
+        [
+            Vec2::new(left, 0.0),
+            Vec2::new(right, 0.0),
+            Vec2::new(right, boid_radius),
+            Vec2::new(left, 0.0),
+            Vec2::new(right, boid_radius),
+            Vec2::new(left, boid_radius),
+        ]
+        .map(|point| point.extend(0.0)),
+    );
+    triangles
+}
+
+pub fn update_batched_boids_renderable(
+    settings: Res<BoidSettings>,
+    render_settings: Res<RenderSettings>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    renderable: Query<&Mesh2dHandle, With<BatchedBoidsRenderable>>,
+    timer: Res<BoidTimer>,
+    boids: Query<
+        (
+            &Position,
+            &Velocity,
+            Option<&InterpolationState>,
+            &BoidColor,
+        ),
+        With<Boid>,
+    >,
+) {
+    if render_settings.backend != RenderBackend::Batched {
+        return;
+    }
+    let Some(mesh) = renderable
+        .get_single()
+        .ok()
+        .and_then(|handle| meshes.get_mut(&handle.0))
+    else {
+        return;
+    };
+
+    let template = get_boid_triangles(settings.boid_radius);
+    let vertex_count = template.len() * boids.iter().len();
+    let mut positions: Vec<[f32; 3]> = Vec::with_capacity(vertex_count);
+    let mut colors: Vec<[f32; 4]> = Vec::with_capacity(vertex_count);
+    let alpha = get_interpolation_alpha(&render_settings, &timer);
+    for (position, velocity, state, boid_color) in boids.iter() {
+        let transform = get_render_transform_for_boid(position, velocity, state, alpha);
+        let color = boid_color.0.as_linear_rgba_f32();
+        for point in template.iter() {
+            positions.push(transform.transform_point(*point).to_array());
+            colors.push(color);
+        }
+    }
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+}
+
+fn get_heat_color(heat: f32) -> [u8; 4] {
+    let heat = heat.clamp(0.0, 1.0);
+    let green = (255.0 * (1.0 - heat)) as u8;
+    let alpha = (200.0 * heat.sqrt()) as u8;
+    [255, green, 0, alpha]
+}
+
+pub fn update_heatmap_renderable(
+    render_settings: Res<RenderSettings>,
+    heatmap: Res<Heatmap>,
+    mut images: ResMut<Assets<Image>>,
+    mut renderable: Query<(&Handle<Image>, &mut Visibility), With<HeatmapRenderable>>,
+) {
+    let Ok((handle, mut visibility)) = renderable.get_single_mut() else {
+        return;
+    };
+    *visibility = if render_settings.show_heatmap {
+        Visibility::Visible
+    } else {
+        Visibility::Hidden
+    };
+    if !render_settings.show_heatmap || !heatmap.is_changed() {
+        return;
+    }
+    let Some(image) = images.get_mut(handle) else {
+        return;
+    };
+
+    let max = heatmap.max().max(f32::EPSILON);
+    for y in 0..heatmap.height {
+        // image rows start at the top, heatmap rows at the bottom
+        let row = heatmap.height - 1 - y;
+        for x in 0..heatmap.width {
+            let heat = heatmap.cells[y * heatmap.width + x] / max;
+            let offset = (row * heatmap.width + x) * 4;
+            image.data[offset..offset + 4].copy_from_slice(&get_heat_color(heat));
+        }
+    }
+}
+
+pub fn draw_trails(
+    trail_settings: Res<TrailSettings>,
+    mut gizmos: Gizmos,
+    boids: Query<(&Position, &Trail, Option<&BoidColor>), With<Boid>>,
+) {
+    if !trail_settings.enabled {
+        return;
+    }
+    for (position, trail, boid_color) in boids.iter() {
+        let color = boid_color.map_or(Color::BLACK, |boid_color| boid_color.0);
+        let count = trail.positions.len();
+        // fade from transparent at the oldest sample to half opaque at the boid
+        let points = trail.positions.iter().chain([&position.0]);
+        for (index, (start, end)) in points.clone().zip(points.skip(1)).enumerate() {
+            let alpha = 0.5 * (index + 1) as f32 / count as f32;
+            gizmos.line_2d(*start, *end, color.with_a(alpha));
+        }
+    }
+}
+
+/// Draws the route through its waypoints and the arrival radius of each waypoint,
+/// the current waypoint is highlighted.
+pub fn draw_route(mut gizmos: Gizmos, route: Res<Route>) {
+    let color = Color::DARK_GREEN;
+    let closed = route.mode == RouteMode::Loop && route.waypoints.len() > 2;
+    for pair in route.waypoints.windows(2) {
+        gizmos.line_2d(pair[0], pair[1], color.with_a(0.5));
+    }
+    if let (Some(first), Some(last)) = (route.waypoints.first(), route.waypoints.last()) {
+        if closed {
+            gizmos.line_2d(*last, *first, color.with_a(0.5));
+        }
+    }
+    for (index, waypoint) in route.waypoints.iter().enumerate() {
+        let color = if route.current == Some(index) {
+            Color::LIME_GREEN
+        } else {
+            color
+        };
+        gizmos.circle_2d(*waypoint, route.arrival_radius, color);
+    }
+}
+
+/// Rings leaders so they stand out from their followers.
+pub fn draw_leaders(
+    settings: Res<BoidSettings>,
+    mut gizmos: Gizmos,
+    leaders: Query<&Position, (With<Boid>, With<Leader>)>,
+) {
+    for position in leaders.iter() {
+        gizmos.circle_2d(position.0, settings.boid_radius * 2.0, Color::GOLD);
+    }
+}
+
+/// Draws the center line and corridor of each path and its control points,
+/// the path being edited is highlighted.
+pub fn draw_paths(mut gizmos: Gizmos, editing: Res<PathEditing>, paths: Query<(Entity, &Path)>) {
+    for (entity, path) in paths.iter() {
+        let color = if editing.active == Some(entity) {
+            Color::SEA_GREEN
+        } else {
+            Color::TEAL
+        };
+        let points = path.points();
+        gizmos.linestrip_2d(points.iter().copied(), color);
+        // corridor edges, offset along the normal of each segment
+        let radius = path.width / 2.0;
+        for segment in points.windows(2) {
+            let normal = (segment[1] - segment[0]).normalize_or_zero().perp() * radius;
+            gizmos.line_2d(segment[0] + normal, segment[1] + normal, color.with_a(0.4));
+            gizmos.line_2d(segment[0] - normal, segment[1] - normal, color.with_a(0.4));
+        }
+        for point in path.control_points.iter() {
+            gizmos.circle_2d(*point, 4.0, color);
+        }
+    }
+}
+
+/// Draws one arrow per flow field cell, a full length vector spans most of the cell.
+pub fn draw_flow_field(
+    render_settings: Res<RenderSettings>,
+    field: Res<FlowField>,
+    mut gizmos: Gizmos,
+) {
+    if !render_settings.show_flow_field {
+        return;
+    }
+    let length = field.cell_size * 0.8;
+    for (index, vector) in field.vectors.iter().enumerate() {
+        let center = field.cell_center(index);
+        let arrow = *vector * length;
+        draw_arrow(
+            &mut gizmos,
+            center - arrow / 2.0,
+            arrow,
+            Color::OLIVE.with_a(0.6),
+        );
+    }
+}
@@ -0,0 +1,192 @@
+use std::f32::consts::PI;
+
+use bevy::{
+    input::mouse::{MouseMotion, MouseScrollUnit, MouseWheel},
+    prelude::{
+        default, AmbientLight, Assets, Camera3dBundle, Color, Commands, Component,
+        DirectionalLight, DirectionalLightBundle, Entity, EventReader, Gizmos, Handle, Input, Mesh,
+        MouseButton, PbrBundle, Quat, Query, Res, ResMut, Resource, StandardMaterial, Transform,
+        Vec2, Vec3, With, Without,
+    },
+    render::render_resource::PrimitiveTopology,
+};
+use bevy_egui::EguiContexts;
+
+use crate::boids::BoidSettings;
+use crate::boids3d::{Boid3d, Boids3dSettings, BoundsShape, Position3d, Velocity3d};
+
+const CONE_SEGMENTS: usize = 12;
+
+/// Camera circling around a focus point, dragging rotates it and scrolling zooms.
+#[derive(Debug, Component)]
+pub struct OrbitCamera {
+    pub focus: Vec3,
+    pub distance: f32,
+    /// Rotation around the vertical axis, in radians.
+    pub yaw: f32,
+    /// Rotation above the horizon, in radians.
+    pub pitch: f32,
+}
+
+impl OrbitCamera {
+    pub fn transform(&self) -> Transform {
+        let rotation = Quat::from_rotation_y(self.yaw) * Quat::from_rotation_x(-self.pitch);
+        Transform::from_translation(self.focus + rotation * Vec3::Z * self.distance)
+            .looking_at(self.focus, Vec3::Y)
+    }
+}
+
+/// Mesh and material shared by all boids.
+#[derive(Resource)]
+pub struct BoidMesh3d {
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+}
+
+/// Cone pointing along +Y with its center at the origin, flat shaded.
+fn get_cone_mesh(radius: f32, height: f32) -> Mesh {
+    let apex = Vec3::Y * height / 2.0;
+    let base = -Vec3::Y * height / 2.0;
+    let rim = |segment: usize| {
+        let angle = segment as f32 / CONE_SEGMENTS as f32 * PI * 2.0;
+        base + Vec3::new(angle.cos(), 0.0, -angle.sin()) * radius
+    };
+
+    let mut positions: Vec<[f32; 3]> = Vec::new();
+    let mut normals: Vec<[f32; 3]> = Vec::new();
+    for segment in 0..CONE_SEGMENTS {
+        let (a, b) = (rim(segment), rim(segment + 1));
+        for triangle in [[apex, a, b], [base, b, a]] {
+            let normal = (triangle[1] - triangle[0])
+                .cross(triangle[2] - triangle[0])
+                .normalize();
+            for vertex in triangle {
+                positions.push(vertex.to_array());
+                normals.push(normal.to_array());
+            }
+        }
+    }
+
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh
+}
+
+pub fn setup_render_3d(
+    mut commands: Commands,
+    settings_3d: Res<Boids3dSettings>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let camera = OrbitCamera {
+        focus: Vec3::ZERO,
+        distance: settings_3d.bounds_size * 3.0,
+        yaw: PI / 6.0,
+        pitch: PI / 8.0,
+    };
+    commands.spawn((
+        Camera3dBundle {
+            transform: camera.transform(),
+            ..default()
+        },
+        camera,
+    ));
+    commands.spawn(DirectionalLightBundle {
+        directional_light: DirectionalLight {
+            illuminance: 20000.0,
+            ..default()
+        },
+        transform: Transform::from_xyz(1.0, 2.0, 1.5).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+    commands.insert_resource(AmbientLight {
+        color: Color::WHITE,
+        brightness: 0.4,
+    });
+
+    commands.insert_resource(BoidMesh3d {
+        mesh: meshes.add(get_cone_mesh(1.0, 3.0)),
+        material: materials.add(Color::ORANGE.into()),
+    });
+}
+
+pub fn spawn_boid_meshes(
+    mut commands: Commands,
+    boid_mesh: Res<BoidMesh3d>,
+    boids: Query<Entity, (With<Boid3d>, Without<Handle<Mesh>>)>,
+) {
+    for entity in boids.iter() {
+        commands.entity(entity).insert(PbrBundle {
+            mesh: boid_mesh.mesh.clone(),
+            material: boid_mesh.material.clone(),
+            ..default()
+        });
+    }
+}
+
+/// Moves the cones to the boids, points them along the velocity and scales the unit cone
+/// to the boid radius.
+pub fn update_boid_transforms_3d(
+    settings: Res<BoidSettings>,
+    mut boids: Query<(&Position3d, &Velocity3d, &mut Transform), With<Boid3d>>,
+) {
+    for (position, velocity, mut transform) in boids.iter_mut() {
+        transform.translation = position.0;
+        transform.scale = Vec3::splat(settings.boid_radius);
+        if let Some(heading) = velocity.0.try_normalize() {
+            transform.rotation = Quat::from_rotation_arc(Vec3::Y, heading);
+        }
+    }
+}
+
+pub fn draw_bounds_3d(settings_3d: Res<Boids3dSettings>, mut gizmos: Gizmos) {
+    let size = settings_3d.bounds_size;
+    match settings_3d.bounds {
+        BoundsShape::Box => {
+            gizmos.cuboid(Transform::from_scale(Vec3::splat(size * 2.0)), Color::GRAY);
+        }
+        BoundsShape::Sphere => {
+            gizmos
+                .sphere(Vec3::ZERO, Quat::IDENTITY, size, Color::GRAY)
+                .circle_segments(48);
+        }
+    }
+}
+
+/// Dragging with the left mouse button orbits the camera, the mouse wheel zooms.
+pub fn update_orbit_camera(
+    buttons: Res<Input<MouseButton>>,
+    mut motion: EventReader<MouseMotion>,
+    mut wheel: EventReader<MouseWheel>,
+    mut contexts: EguiContexts,
+    mut cameras: Query<(&mut OrbitCamera, &mut Transform)>,
+) {
+    let egui_wants_pointer = contexts.ctx_mut().wants_pointer_input();
+    let moved: Vec2 = motion.iter().map(|event| event.delta).sum();
+    let scrolled: f32 = wheel
+        .iter()
+        .map(|event| match event.unit {
+            MouseScrollUnit::Line => event.y,
+            MouseScrollUnit::Pixel => event.y / 20.0,
+        })
+        .sum();
+    if egui_wants_pointer {
+        return;
+    }
+
+    for (mut camera, mut transform) in cameras.iter_mut() {
+        if buttons.pressed(MouseButton::Left) && moved != Vec2::ZERO {
+            camera.yaw -= moved.x * 0.005;
+            camera.pitch =
+                (camera.pitch + moved.y * 0.005).clamp(-PI / 2.0 + 0.01, PI / 2.0 - 0.01);
+        }
+        if scrolled != 0.0 {
+            camera.distance = (camera.distance * 0.9f32.powf(scrolled)).clamp(10.0, 10000.0);
+        }
+        let orbit = camera.transform();
+        if *transform != orbit {
+            *transform = orbit;
+        }
+    }
+}
@@ -0,0 +1,276 @@
+use bevy::{
+    prelude::{
+        info, Camera, Commands, Component, Entity, GlobalTransform, Input, KeyCode, MouseButton,
+        OrthographicProjection, Query, Res, ResMut, Resource, Vec2, With,
+    },
+    window::{PrimaryWindow, Window},
+};
+use bevy_egui::EguiContexts;
+
+use crate::boids::{Boid, BoidTimer, Position};
+use crate::render::{get_cursor_world_position, MainCamera2d};
+use crate::targets::{Falloff, Target, TargetBehavior};
+
+/// Clicks closer than this many pixels to a waypoint delete it.
+const PICK_DISTANCE: f32 = 20.0;
+
+/// Radius of the route target, large enough to pull boids from anywhere in the arena.
+const ROUTE_TARGET_RADIUS: f32 = 10000.0;
+
+/// What happens after the last waypoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouteMode {
+    /// Stop after the last waypoint.
+    Once,
+    /// Continue with the first waypoint.
+    Loop,
+    /// Visit the waypoints backwards, then forwards again.
+    PingPong,
+}
+
+impl RouteMode {
+    pub const ALL: [RouteMode; 3] = [RouteMode::Once, RouteMode::Loop, RouteMode::PingPong];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            RouteMode::Once => "Once",
+            RouteMode::Loop => "Loop",
+            RouteMode::PingPong => "Ping-Pong",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "once" => Some(RouteMode::Once),
+            "loop" => Some(RouteMode::Loop),
+            "ping-pong" => Some(RouteMode::PingPong),
+            _ => None,
+        }
+    }
+}
+
+/// When the flock counts as arrived at a waypoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arrival {
+    /// The centroid of all boids is within the arrival radius.
+    Centroid,
+    /// The arrival fraction of boids is within the arrival radius.
+    Fraction,
+}
+
+/// Waypoints the flock visits in order, the current one is sought like a target.
+#[derive(Debug, Resource)]
+pub struct Route {
+    pub waypoints: Vec<Vec2>,
+    pub mode: RouteMode,
+    pub arrival: Arrival,
+    pub arrival_radius: f32,
+    /// Fraction of boids that has to arrive for `Arrival::Fraction`.
+    pub arrival_fraction: f32,
+    /// Weight of the route target, see `Target::weight`.
+    pub weight: f32,
+    /// Index of the waypoint the flock is heading to, None if the route is stopped.
+    pub current: Option<usize>,
+    /// Number of waypoints reached since the route was started.
+    pub reached: u64,
+    reverse: bool,
+}
+
+impl Default for Route {
+    fn default() -> Self {
+        Self {
+            waypoints: Vec::new(),
+            mode: RouteMode::Loop,
+            arrival: Arrival::Centroid,
+            arrival_radius: 100.0,
+            arrival_fraction: 0.5,
+            weight: 1.0,
+            current: None,
+            reached: 0,
+            reverse: false,
+        }
+    }
+}
+
+impl Route {
+    /// Parses waypoints written as `x,y;x,y;...`.
+    pub fn parse_waypoints(value: &str) -> Option<Vec<Vec2>> {
+        value
+            .split(';')
+            .map(|waypoint| {
+                let (x, y) = waypoint.split_once(',')?;
+                Some(Vec2::new(x.trim().parse().ok()?, y.trim().parse().ok()?))
+            })
+            .collect()
+    }
+
+    /// Heads to the first waypoint, if there is one.
+    pub fn start(&mut self) {
+        self.current = (!self.waypoints.is_empty()).then_some(0);
+        self.reached = 0;
+        self.reverse = false;
+    }
+
+    pub fn stop(&mut self) {
+        self.current = None;
+    }
+
+    /// Position of the waypoint the flock is heading to.
+    pub fn current_waypoint(&self) -> Option<Vec2> {
+        self.current
+            .and_then(|index| self.waypoints.get(index))
+            .copied()
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        self.waypoints.remove(index);
+        match self.current {
+            Some(current) if current > index => self.current = Some(current - 1),
+            Some(current) if current >= self.waypoints.len() => self.start(),
+            _ => {}
+        }
+    }
+
+    fn arrived(&self, waypoint: Vec2, positions: &[Vec2]) -> bool {
+        if positions.is_empty() {
+            return false;
+        }
+        match self.arrival {
+            Arrival::Centroid => {
+                let centroid = positions.iter().sum::<Vec2>() / positions.len() as f32;
+                centroid.distance(waypoint) < self.arrival_radius
+            }
+            Arrival::Fraction => {
+                let arrived = positions
+                    .iter()
+                    .filter(|position| position.distance(waypoint) < self.arrival_radius)
+                    .count();
+                arrived as f32 >= self.arrival_fraction * positions.len() as f32
+            }
+        }
+    }
+
+    fn advance(&mut self) {
+        let Some(current) = self.current else {
+            return;
+        };
+        let last = self.waypoints.len().saturating_sub(1);
+        self.current = match self.mode {
+            RouteMode::Once => (current < last).then_some(current + 1),
+            RouteMode::Loop => Some(if current < last { current + 1 } else { 0 }),
+            RouteMode::PingPong => {
+                if last == 0 {
+                    Some(0)
+                } else {
+                    if (self.reverse && current == 0) || (!self.reverse && current == last) {
+                        self.reverse = !self.reverse;
+                    }
+                    Some(if self.reverse {
+                        current - 1
+                    } else {
+                        current + 1
+                    })
+                }
+            }
+        };
+    }
+}
+
+/// Marks the target that follows the current waypoint of the route.
+#[derive(Component)]
+pub struct RouteTarget;
+
+/// Moves the route target to the current waypoint and advances the route once the
+/// flock arrived.
+pub fn update_route(
+    mut commands: Commands,
+    timer: Res<BoidTimer>,
+    mut route: ResMut<Route>,
+    boids: Query<&Position, With<Boid>>,
+    mut targets: Query<(Entity, &mut Target), With<RouteTarget>>,
+) {
+    if timer.finished() {
+        if let Some(waypoint) = route.current_waypoint() {
+            let positions: Vec<Vec2> = boids.iter().map(|position| position.0).collect();
+            if route.arrived(waypoint, &positions) {
+                route.reached += 1;
+                info!(
+                    "reached waypoint {} at ({:.0}, {:.0})",
+                    route.current.unwrap_or_default(),
+                    waypoint.x,
+                    waypoint.y
+                );
+                route.advance();
+                if route.current.is_none() {
+                    info!("finished route after {} waypoints", route.reached);
+                }
+            }
+        }
+    }
+
+    // only write the target when it moves, so its renderable is not updated every frame
+    match (route.current_waypoint(), targets.get_single_mut()) {
+        (Some(waypoint), Ok((_, mut target))) => {
+            if target.position != waypoint || target.weight != route.weight {
+                target.position = waypoint;
+                target.weight = route.weight;
+            }
+        }
+        (Some(waypoint), Err(_)) => {
+            commands.spawn((
+                Target {
+                    position: waypoint,
+                    velocity: Vec2::ZERO,
+                    behavior: TargetBehavior::Seek,
+                    weight: route.weight,
+                    radius: ROUTE_TARGET_RADIUS,
+                    falloff: Falloff::Constant,
+                },
+                RouteTarget,
+            ));
+        }
+        (None, Ok((entity, _))) => commands.entity(entity).despawn(),
+        (None, Err(_)) => {}
+    }
+}
+
+/// Shift and middle click appends a waypoint, shift and right click deletes the one
+/// under the cursor.
+pub fn update_route_from_mouse(
+    buttons: Res<Input<MouseButton>>,
+    keys: Res<Input<KeyCode>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera_transform: Query<
+        (&Camera, &GlobalTransform, &OrthographicProjection),
+        With<MainCamera2d>,
+    >,
+    mut contexts: EguiContexts,
+    mut route: ResMut<Route>,
+) {
+    if !keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight])
+        || contexts.ctx_mut().wants_pointer_input()
+    {
+        return;
+    }
+    let (camera, camera_transform, projection) = camera_transform.single();
+    let window = windows.get_single().unwrap();
+    let Some(cursor) = get_cursor_world_position(window, camera, camera_transform) else {
+        return;
+    };
+
+    if buttons.just_pressed(MouseButton::Middle) {
+        route.waypoints.push(cursor);
+    } else if buttons.just_pressed(MouseButton::Right) {
+        let closest = route
+            .waypoints
+            .iter()
+            .enumerate()
+            .map(|(index, waypoint)| (index, waypoint.distance(cursor)))
+            .filter(|(_, distance)| *distance < PICK_DISTANCE * projection.scale)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(index, _)| index);
+        if let Some(index) = closest {
+            route.remove(index);
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use bevy::{
+    ecs::system::SystemParam,
+    prelude::{Color, Query, Res, Vec2, With},
+};
+
+use crate::boids::{Boid, BoidSettings, Position, Velocity};
+use crate::render::BoidColor;
+use crate::targets::Target;
+
+/// Boid data needed to draw a frame.
+pub struct SceneBoid {
+    pub position: Vec2,
+    /// Unit vector the heading line points along.
+    pub heading: Vec2,
+    pub color: Color,
+}
+
+/// Snapshot of everything the file exporters draw, taken from ECS state only
+/// so it works without a window.
+pub struct Scene {
+    pub boundary_min: Vec2,
+    pub boundary_max: Vec2,
+    pub boid_radius: f32,
+    pub targets: Vec<Target>,
+    pub boids: Vec<SceneBoid>,
+}
+
+impl Scene {
+    /// Radius of the target circle, shared with `spawn_target_renderable`.
+    pub const TARGET_RADIUS: f32 = 10.0;
+    /// Space around the walls included in exported frames.
+    pub const MARGIN: f32 = 20.0;
+
+    pub fn min(&self) -> Vec2 {
+        self.boundary_min - Self::MARGIN
+    }
+
+    pub fn max(&self) -> Vec2 {
+        self.boundary_max + Self::MARGIN
+    }
+}
+
+#[derive(SystemParam)]
+pub struct SceneQuery<'w, 's> {
+    settings: Res<'w, BoidSettings>,
+    targets: Query<'w, 's, &'static Target>,
+    boids: Query<
+        'w,
+        's,
+        (
+            &'static Position,
+            &'static Velocity,
+            Option<&'static BoidColor>,
+        ),
+        With<Boid>,
+    >,
+}
+
+impl<'w, 's> SceneQuery<'w, 's> {
+    pub fn scene(&self) -> Scene {
+        Scene {
+            boundary_min: Vec2::new(self.settings.boundary_min_x, self.settings.boundary_min_y),
+            boundary_max: Vec2::new(self.settings.boundary_max_x, self.settings.boundary_max_y),
+            boid_radius: self.settings.boid_radius,
+            targets: self.targets.iter().copied().collect(),
+            boids: self
+                .boids
+                .iter()
+                .map(|(position, velocity, color)| SceneBoid {
+                    position: position.0,
+                    // boids without velocity point up, like in `get_transform_for_boid`
+                    heading: velocity.0.try_normalize().unwrap_or(Vec2::Y),
+                    color: color.map_or(Color::BLACK, |color| color.0),
+                })
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,512 @@
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Sub, SubAssign};
+
+use bevy::prelude::{Vec2, Vec3};
+
+/// Vector the steering behaviors that don't depend on the dimension work with,
+/// implemented for the 2D and the 3D simulation.
+pub trait SteeringVector:
+    Copy
+    + PartialEq
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<f32, Output = Self>
+    + Div<f32, Output = Self>
+    + AddAssign
+    + SubAssign
+    + MulAssign<f32>
+    + DivAssign<f32>
+{
+    const ZERO: Self;
+    /// Direction of a boid without a velocity.
+    const UP: Self;
+
+    fn length(self) -> f32;
+    fn normalize(self) -> Self;
+    fn distance(self, other: Self) -> f32;
+    /// Cross product, in 2D the z axis holds the perp dot product.
+    fn cross(self, other: Self) -> Vec3;
+}
+
+impl SteeringVector for Vec2 {
+    const ZERO: Self = Vec2::ZERO;
+    const UP: Self = Vec2::Y;
+
+    fn length(self) -> f32 {
+        Vec2::length(self)
+    }
+
+    fn normalize(self) -> Self {
+        Vec2::normalize(self)
+    }
+
+    fn distance(self, other: Self) -> f32 {
+        Vec2::distance(self, other)
+    }
+
+    fn cross(self, other: Self) -> Vec3 {
+        Vec3::Z * self.perp_dot(other)
+    }
+}
+
+impl SteeringVector for Vec3 {
+    const ZERO: Self = Vec3::ZERO;
+    const UP: Self = Vec3::Y;
+
+    fn length(self) -> f32 {
+        Vec3::length(self)
+    }
+
+    fn normalize(self) -> Self {
+        Vec3::normalize(self)
+    }
+
+    fn distance(self, other: Self) -> f32 {
+        Vec3::distance(self, other)
+    }
+
+    fn cross(self, other: Self) -> Vec3 {
+        Vec3::cross(self, other)
+    }
+}
+
+pub fn limit_length<V: SteeringVector>(vector: V, max_length: f32) -> V {
+    if vector.length() > max_length {
+        vector.normalize() * max_length
+    } else {
+        vector
+    }
+}
+
+/// Lengthens vectors shorter than min_length, a zero vector points up
+pub fn limit_length_min<V: SteeringVector>(vector: V, min_length: f32) -> V {
+    if vector.length() < min_length {
+        let direction = if vector == V::ZERO {
+            V::UP
+        } else {
+            vector.normalize()
+        };
+        direction * min_length
+    } else {
+        vector
+    }
+}
+
+/// Steering towards a desired velocity, limited to the maximum force
+fn steer_towards<V: SteeringVector>(desired: V, velocity: V, max_force: f32) -> V {
+    limit_length(desired - velocity, max_force)
+}
+
+/// Seek, steer towards a target at full speed
+///
+/// Arguments:
+/// position: the current position of this boid
+/// velocity: the current velocity of this boid
+/// target: the position to move to
+/// max_speed: the maximum speed of this boid
+/// max_force: the maximum force that can be applied to this boid
+///
+/// Returns: seek force vector
+pub fn get_seek_force<V: SteeringVector>(
+    position: V,
+    velocity: V,
+    target: V,
+    max_speed: f32,
+    max_force: f32,
+) -> V {
+    let desired = target - position;
+    if desired == V::ZERO {
+        return V::ZERO;
+    }
+    steer_towards(desired.normalize() * max_speed, velocity, max_force)
+}
+
+/// Flee, steer away from a target at full speed while it is within the flee radius
+///
+/// Arguments:
+/// position: the current position of this boid
+/// velocity: the current velocity of this boid
+/// target: the position to move away from
+/// flee_radius: distance to the target beyond which the boid stops fleeing
+/// max_speed: the maximum speed of this boid
+/// max_force: the maximum force that can be applied to this boid
+///
+/// Returns: flee force vector, zero outside the flee radius
+pub fn get_flee_force<V: SteeringVector>(
+    position: V,
+    velocity: V,
+    target: V,
+    flee_radius: f32,
+    max_speed: f32,
+    max_force: f32,
+) -> V {
+    let desired = position - target;
+    if desired == V::ZERO || desired.length() >= flee_radius {
+        return V::ZERO;
+    }
+    steer_towards(desired.normalize() * max_speed, velocity, max_force)
+}
+
+/// Arrival, seek a target but slow down inside the slowing radius so the target
+/// is reached without overshooting
+///
+/// Arguments:
+/// position: the current position of this boid
+/// velocity: the current velocity of this boid
+/// target: the position to stop at
+/// slowing_radius: distance to the target at which to start slowing down
+/// max_speed: the maximum speed of this boid
+/// max_force: the maximum force that can be applied to this boid
+///
+/// Returns: arrival force vector, it brakes the boid once the target is reached
+pub fn get_arrival_force<V: SteeringVector>(
+    position: V,
+    velocity: V,
+    target: V,
+    slowing_radius: f32,
+    max_speed: f32,
+    max_force: f32,
+) -> V {
+    let offset = target - position;
+    let distance = offset.length();
+    if distance == 0.0 {
+        return steer_towards(V::ZERO, velocity, max_force);
+    }
+    let speed = if distance < slowing_radius {
+        max_speed * distance / slowing_radius
+    } else {
+        max_speed
+    };
+    steer_towards(offset / distance * speed, velocity, max_force)
+}
+
+/// Predicts where a moving target will be once a boid at the given distance reaches it,
+/// looking at most max_prediction ahead, in the time unit the velocities move per.
+///
+/// Arguments:
+/// position: the current position of this boid
+/// target: the current position of the target
+/// target_velocity: the current velocity of the target
+/// max_speed: the maximum speed of this boid
+/// max_prediction: upper bound for the time to look ahead
+///
+/// Returns: predicted target position
+pub fn predict_position<V: SteeringVector>(
+    position: V,
+    target: V,
+    target_velocity: V,
+    max_speed: f32,
+    max_prediction: f32,
+) -> V {
+    let distance = position.distance(target);
+    let prediction = if max_speed > 0.0 {
+        (distance / max_speed).min(max_prediction)
+    } else {
+        max_prediction
+    };
+    target + target_velocity * prediction
+}
+
+/// Pursuit, seek the predicted future position of a moving target
+///
+/// Arguments:
+/// position: the current position of this boid
+/// velocity: the current velocity of this boid
+/// target: the current position of the target
+/// target_velocity: the current velocity of the target
+/// max_prediction: upper bound for the time to look ahead
+/// max_speed: the maximum speed of this boid
+/// max_force: the maximum force that can be applied to this boid
+///
+/// Returns: pursuit force vector
+pub fn get_pursuit_force<V: SteeringVector>(
+    position: V,
+    velocity: V,
+    target: V,
+    target_velocity: V,
+    max_prediction: f32,
+    max_speed: f32,
+    max_force: f32,
+) -> V {
+    let predicted = predict_position(position, target, target_velocity, max_speed, max_prediction);
+    get_seek_force(position, velocity, predicted, max_speed, max_force)
+}
+
+/// Evasion, flee from the predicted future position of a moving target
+///
+/// Arguments:
+/// position: the current position of this boid
+/// velocity: the current velocity of this boid
+/// target: the current position of the target
+/// target_velocity: the current velocity of the target
+/// max_prediction: upper bound for the time to look ahead
+/// flee_radius: distance to the predicted position beyond which the boid stops fleeing
+/// max_speed: the maximum speed of this boid
+/// max_force: the maximum force that can be applied to this boid
+///
+/// Returns: evasion force vector
+#[allow(clippy::too_many_arguments)]
+pub fn get_evasion_force<V: SteeringVector>(
+    position: V,
+    velocity: V,
+    target: V,
+    target_velocity: V,
+    max_prediction: f32,
+    flee_radius: f32,
+    max_speed: f32,
+    max_force: f32,
+) -> V {
+    let predicted = predict_position(position, target, target_velocity, max_speed, max_prediction);
+    get_flee_force(
+        position,
+        velocity,
+        predicted,
+        flee_radius,
+        max_speed,
+        max_force,
+    )
+}
+
+/// Wander, seek a point on a circle ahead of the boid, the point drifts randomly
+/// around the circle between ticks so the heading changes smoothly
+///
+/// Arguments:
+/// position: the current position of this boid
+/// velocity: the current velocity of this boid
+/// wander_angle: angle of the point on the circle, relative to the heading
+/// circle_distance: how far ahead of the boid the circle is
+/// circle_radius: the radius of the circle
+/// max_speed: the maximum speed of this boid
+/// max_force: the maximum force that can be applied to this boid
+///
+/// Returns: wander force vector
+pub fn get_wander_force(
+    position: Vec2,
+    velocity: Vec2,
+    wander_angle: f32,
+    circle_distance: f32,
+    circle_radius: f32,
+    max_speed: f32,
+    max_force: f32,
+) -> Vec2 {
+    let heading = velocity.try_normalize().unwrap_or(Vec2::Y);
+    let center = position + heading * circle_distance;
+    let target = center + heading.rotate(Vec2::from_angle(wander_angle)) * circle_radius;
+    get_seek_force(position, velocity, target, max_speed, max_force)
+}
+
+/// Leader following, arrive at a point behind the leader and evade it when standing
+/// in front of it, so followers trail the leader without blocking its way
+///
+/// Arguments:
+/// position: the current position of this boid
+/// velocity: the current velocity of this boid
+/// leader: the current position of the leader
+/// leader_velocity: the current velocity of the leader
+/// follow_distance: how far behind the leader to stay
+/// sight_radius: followers this close to the point ahead of the leader move out of its way
+/// slowing_radius: distance at which to start slowing down when arriving behind the leader
+/// max_speed: the maximum speed of this boid
+/// max_force: the maximum force that can be applied to this boid
+///
+/// Returns: follow force vector
+#[allow(clippy::too_many_arguments)]
+pub fn get_follow_force(
+    position: Vec2,
+    velocity: Vec2,
+    leader: Vec2,
+    leader_velocity: Vec2,
+    follow_distance: f32,
+    sight_radius: f32,
+    slowing_radius: f32,
+    max_speed: f32,
+    max_force: f32,
+) -> Vec2 {
+    let heading = leader_velocity.try_normalize().unwrap_or(Vec2::Y);
+    let behind = leader - heading * follow_distance;
+    let ahead = leader + heading * follow_distance;
+
+    let mut force = get_arrival_force(
+        position,
+        velocity,
+        behind,
+        slowing_radius,
+        max_speed,
+        max_force,
+    );
+    if position.distance(ahead) < sight_radius || position.distance(leader) < sight_radius {
+        force += get_evasion_force(
+            position,
+            velocity,
+            leader,
+            leader_velocity,
+            0.0,
+            f32::INFINITY,
+            max_speed,
+            max_force,
+        );
+    }
+    force
+}
+
+/// Path following, steer back towards the center line once the predicted position
+/// leaves the corridor, aiming ahead along the path
+///
+/// Arguments:
+/// position: the current position of this boid
+/// velocity: the current velocity of this boid
+/// points: the center line of the path, in the direction to follow
+/// radius: half the width of the corridor
+/// lookahead: how far ahead to predict the position and to aim along the path
+/// max_speed: the maximum speed of this boid
+/// max_force: the maximum force that can be applied to this boid
+///
+/// Returns: path following force vector and the distance of the predicted position to
+/// the center line, None if the path has no segments
+pub fn get_path_following_force(
+    position: Vec2,
+    velocity: Vec2,
+    points: &[Vec2],
+    radius: f32,
+    lookahead: f32,
+    max_speed: f32,
+    max_force: f32,
+) -> Option<(Vec2, f32)> {
+    let heading = velocity.try_normalize().unwrap_or(Vec2::Y);
+    let predicted = position + heading * lookahead;
+
+    // closest point on the center line and the direction of its segment
+    let (normal_point, direction, distance) = points
+        .windows(2)
+        .filter_map(|segment| {
+            let offset = segment[1] - segment[0];
+            let direction = offset.try_normalize()?;
+            let along = (predicted - segment[0])
+                .dot(direction)
+                .clamp(0.0, offset.length());
+            let normal_point = segment[0] + direction * along;
+            Some((normal_point, direction, normal_point.distance(predicted)))
+        })
+        .min_by(|a, b| a.2.total_cmp(&b.2))?;
+
+    let force = if distance > radius {
+        let target = normal_point + direction * lookahead;
+        get_seek_force(position, velocity, target, max_speed, max_force)
+    } else {
+        Vec2::ZERO
+    };
+    Some((force, distance))
+}
+
+/// Flow field following, steer to move along the field at the current position
+///
+/// Arguments:
+/// velocity: the current velocity of this boid
+/// flow: the field at the position of this boid, shorter than 1.0 for weaker flow
+/// max_speed: the maximum speed of this boid
+/// max_force: the maximum force that can be applied to this boid
+///
+/// Returns: flow force vector
+pub fn get_flow_force(velocity: Vec2, flow: Vec2, max_speed: f32, max_force: f32) -> Vec2 {
+    if flow == Vec2::ZERO {
+        return Vec2::ZERO;
+    }
+    steer_towards(flow.clamp_length_max(1.0) * max_speed, velocity, max_force)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arrival_slows_down_inside_the_slowing_radius() {
+        let target = Vec2::new(100.0, 0.0);
+        // at rest, the force is the desired velocity as long as it stays below max_force
+        let outside = get_arrival_force(Vec2::ZERO, Vec2::ZERO, target, 50.0, 2.0, 10.0);
+        let inside = get_arrival_force(Vec2::new(75.0, 0.0), Vec2::ZERO, target, 50.0, 2.0, 10.0);
+        assert_eq!(outside, Vec2::new(2.0, 0.0));
+        assert_eq!(inside, Vec2::new(1.0, 0.0));
+        // on the target it brakes
+        let on_target = get_arrival_force(target, Vec2::new(1.0, 0.0), target, 50.0, 2.0, 10.0);
+        assert_eq!(on_target, Vec2::new(-1.0, 0.0));
+    }
+
+    #[test]
+    fn flee_is_zero_outside_its_radius() {
+        let target = Vec2::ZERO;
+        let near = get_flee_force(Vec2::new(10.0, 0.0), Vec2::ZERO, target, 50.0, 2.0, 10.0);
+        let far = get_flee_force(Vec2::new(60.0, 0.0), Vec2::ZERO, target, 50.0, 2.0, 10.0);
+        assert_eq!(near, Vec2::new(2.0, 0.0));
+        assert_eq!(far, Vec2::ZERO);
+    }
+
+    #[test]
+    fn pursuit_and_evasion_aim_at_the_predicted_position() {
+        let position = Vec2::ZERO;
+        let target = Vec2::new(20.0, 0.0);
+        let target_velocity = Vec2::new(0.0, 1.0);
+        // 20 px at a max speed of 2 px per tick, the target moves 10 ticks ahead
+        let predicted = predict_position(position, target, target_velocity, 2.0, 50.0);
+        assert_eq!(predicted, Vec2::new(20.0, 10.0));
+        assert_eq!(
+            predict_position(position, target, target_velocity, 2.0, 4.0),
+            Vec2::new(20.0, 4.0)
+        );
+
+        let pursuit = get_pursuit_force(
+            position,
+            Vec2::ZERO,
+            target,
+            target_velocity,
+            50.0,
+            2.0,
+            10.0,
+        );
+        let evasion = get_evasion_force(
+            position,
+            Vec2::ZERO,
+            target,
+            target_velocity,
+            50.0,
+            100.0,
+            2.0,
+            10.0,
+        );
+        assert!((pursuit - predicted.normalize() * 2.0).length() < 1e-5);
+        assert!((evasion + predicted.normalize() * 2.0).length() < 1e-5);
+    }
+
+    #[test]
+    fn prediction_of_a_resting_target_is_its_position() {
+        let target = Vec2::new(30.0, -5.0);
+        assert_eq!(
+            predict_position(Vec2::ZERO, target, Vec2::ZERO, 2.0, 50.0),
+            target
+        );
+        // a boid that cannot move looks the full prediction ahead
+        assert_eq!(
+            predict_position(Vec2::ZERO, target, Vec2::X, 0.0, 50.0),
+            target + Vec2::X * 50.0
+        );
+        let pursuit =
+            get_pursuit_force(Vec2::ZERO, Vec2::ZERO, target, Vec2::ZERO, 50.0, 2.0, 10.0);
+        assert!((pursuit - target.normalize() * 2.0).length() < 1e-5);
+    }
+
+    #[test]
+    fn target_behaviors_work_in_3d() {
+        let target = Vec3::new(0.0, 0.0, 100.0);
+        let inside = get_arrival_force(
+            Vec3::new(0.0, 0.0, 75.0),
+            Vec3::ZERO,
+            target,
+            50.0,
+            2.0,
+            10.0,
+        );
+        assert_eq!(inside, Vec3::new(0.0, 0.0, 1.0));
+        let far = get_flee_force(Vec3::ZERO, Vec3::ZERO, target, 50.0, 2.0, 10.0);
+        assert_eq!(far, Vec3::ZERO);
+        let predicted = predict_position(Vec3::ZERO, target, Vec3::X, 10.0, 50.0);
+        assert_eq!(predicted, Vec3::new(10.0, 0.0, 100.0));
+    }
+}
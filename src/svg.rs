@@ -0,0 +1,125 @@
+use std::fmt::Write as _;
+
+use bevy::prelude::{error, info, Color, Res, ResMut, Resource};
+
+use crate::boids::BoidTimer;
+use crate::metrics::FlockMetrics;
+use crate::scene::{Scene, SceneQuery};
+
+/// Writes the scene to SVG files, on request or every few ticks.
+#[derive(Debug, Resource)]
+pub struct SvgExport {
+    /// File name, `{tick}` is replaced with the current tick.
+    pub path: String,
+    /// Write a file every this many ticks, 0 disables periodic export.
+    pub every: u64,
+    /// Write a file on the next frame, set by the UI.
+    pub requested: bool,
+}
+
+impl Default for SvgExport {
+    fn default() -> Self {
+        Self {
+            path: "frame_{tick}.svg".into(),
+            every: 0,
+            requested: false,
+        }
+    }
+}
+
+fn to_svg_color(color: Color) -> String {
+    let [r, g, b, _] = color.as_rgba_u8();
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+/// Builds an SVG document of the arena, the targets and the boids.
+///
+/// Boids are drawn as a circle and a heading line like `spawn_boid_renderable`,
+/// the y axis is flipped so the image matches the window.
+pub fn build_svg(scene: &Scene) -> String {
+    let min = scene.min();
+    let max = scene.max();
+    let size = max - min;
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}" width="{}" height="{}">"#,
+        min.x, -max.y, size.x, size.y, size.x, size.y
+    );
+    let _ = writeln!(
+        svg,
+        r#"<rect x="{}" y="{}" width="{}" height="{}" fill="white"/>"#,
+        min.x, -max.y, size.x, size.y
+    );
+    let _ = writeln!(svg, r#"<g transform="scale(1,-1)" fill="none">"#);
+
+    // walls
+    let walls = scene.boundary_max - scene.boundary_min;
+    let _ = writeln!(
+        svg,
+        r#"<rect x="{}" y="{}" width="{}" height="{}" stroke="blue" stroke-width="1"/>"#,
+        scene.boundary_min.x, scene.boundary_min.y, walls.x, walls.y
+    );
+
+    // targets, a circle with a cross
+    for target in scene.targets.iter() {
+        let radius = Scene::TARGET_RADIUS;
+        let _ = writeln!(
+            svg,
+            r#"<g stroke="{color}" stroke-width="1"><circle cx="{x}" cy="{y}" r="{r}"/><line x1="{x0}" y1="{y0}" x2="{x1}" y2="{y1}"/><line x1="{x0}" y1="{y1}" x2="{x1}" y2="{y0}"/></g>"#,
+            color = to_svg_color(target.color()),
+            x = target.position.x,
+            y = target.position.y,
+            r = radius,
+            x0 = target.position.x - radius,
+            y0 = target.position.y - radius,
+            x1 = target.position.x + radius,
+            y1 = target.position.y + radius,
+        );
+    }
+
+    // boids, the heading line points along the velocity
+    let radius = scene.boid_radius;
+    for boid in scene.boids.iter() {
+        let heading = boid.position + boid.heading * radius;
+        let _ = writeln!(
+            svg,
+            r#"<g stroke="{color}" stroke-width="2"><circle cx="{x}" cy="{y}" r="{r}"/><line x1="{x}" y1="{y}" x2="{hx}" y2="{hy}"/></g>"#,
+            color = to_svg_color(boid.color),
+            x = boid.position.x,
+            y = boid.position.y,
+            r = radius,
+            hx = heading.x,
+            hy = heading.y,
+        );
+    }
+
+    let _ = writeln!(svg, "</g>");
+    let _ = writeln!(svg, "</svg>");
+    svg
+}
+
+pub fn export_svg(
+    timer: Res<BoidTimer>,
+    metrics: Res<FlockMetrics>,
+    mut export: ResMut<SvgExport>,
+    scene: SceneQuery,
+) {
+    let periodic =
+        export.every > 0 && timer.finished() && metrics.tick.is_multiple_of(export.every);
+    if !export.requested && !periodic {
+        return;
+    }
+    export.requested = false;
+
+    let svg = build_svg(&scene.scene());
+
+    let path = export
+        .path
+        .replace("{tick}", &format!("{:06}", metrics.tick));
+    match std::fs::write(&path, svg) {
+        Ok(()) => info!("wrote {}", path),
+        Err(err) => error!("could not write {}: {}", path, err),
+    }
+}
@@ -0,0 +1,254 @@
+use std::f32::consts::PI;
+
+use bevy::time::Time;
+use bevy::{
+    prelude::{
+        Camera, Color, Commands, Component, Entity, GlobalTransform, Input, KeyCode, MouseButton,
+        OrthographicProjection, Query, Res, ResMut, Resource, Vec2, With, Without,
+    },
+    window::{PrimaryWindow, Window},
+};
+use bevy_egui::EguiContexts;
+use rand::Rng;
+
+use crate::boids::{frame_ticks, BoidSettings};
+use crate::render::{get_cursor_world_position, MainCamera2d};
+use crate::routes::RouteTarget;
+
+/// Clicks closer than this many pixels to a target grab or delete it.
+const PICK_DISTANCE: f32 = 20.0;
+
+/// How the pull of a target fades between its center and its radius.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Falloff {
+    /// Full strength everywhere inside the radius.
+    Constant,
+    Linear,
+    /// Smoothstep, strong near the center and fading softly at the edge.
+    Smooth,
+}
+
+impl Falloff {
+    pub const ALL: [Falloff; 3] = [Falloff::Constant, Falloff::Linear, Falloff::Smooth];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Falloff::Constant => "Constant",
+            Falloff::Linear => "Linear",
+            Falloff::Smooth => "Smooth",
+        }
+    }
+
+    /// Strength at a distance given as a fraction of the radius, 0.0 outside of it.
+    pub fn factor(&self, distance: f32) -> f32 {
+        if !(0.0..1.0).contains(&distance) {
+            return 0.0;
+        }
+        match self {
+            Falloff::Constant => 1.0,
+            Falloff::Linear => 1.0 - distance,
+            Falloff::Smooth => {
+                let t = 1.0 - distance;
+                t * t * (3.0 - 2.0 * t)
+            }
+        }
+    }
+}
+
+/// How boids steer towards a target, targets with negative weight are steered away from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TargetBehavior {
+    /// Seek at full speed, or flee.
+    Seek,
+    /// Slow down inside the arrival radius, or flee.
+    Arrive,
+    /// Seek the predicted position of the moving target, or evade it.
+    Pursue,
+}
+
+impl TargetBehavior {
+    pub const ALL: [TargetBehavior; 3] = [
+        TargetBehavior::Seek,
+        TargetBehavior::Arrive,
+        TargetBehavior::Pursue,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TargetBehavior::Seek => "Seek / Flee",
+            TargetBehavior::Arrive => "Arrive / Flee",
+            TargetBehavior::Pursue => "Pursue / Evade",
+        }
+    }
+}
+
+/// A point that attracts boids, or repels them if the weight is negative.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct Target {
+    pub position: Vec2,
+    /// Distance in px moved per simulation tick, targets bounce off the walls.
+    pub velocity: Vec2,
+    pub behavior: TargetBehavior,
+    /// Multiplies the seek weight, negative values make boids flee.
+    pub weight: f32,
+    /// Boids further away than this are not affected.
+    pub radius: f32,
+    pub falloff: Falloff,
+}
+
+impl Target {
+    /// Signed strength of the target on a boid at the given position.
+    pub fn influence(&self, position: Vec2) -> f32 {
+        if self.radius <= 0.0 {
+            return 0.0;
+        }
+        let distance = position.distance(self.position) / self.radius;
+        self.weight * self.falloff.factor(distance)
+    }
+
+    pub fn color(&self) -> Color {
+        if self.weight < 0.0 {
+            Color::PURPLE
+        } else {
+            Color::RED
+        }
+    }
+}
+
+/// Properties of targets placed with the mouse.
+#[derive(Debug, Resource)]
+pub struct TargetSettings {
+    pub behavior: TargetBehavior,
+    /// Speed of new targets in a random direction in px per tick, 0.0 for static targets.
+    pub speed: f32,
+    pub weight: f32,
+    pub radius: f32,
+    pub falloff: Falloff,
+}
+
+impl Default for TargetSettings {
+    fn default() -> Self {
+        Self {
+            behavior: TargetBehavior::Seek,
+            speed: 0.0,
+            weight: 1.0,
+            // covers the whole arena, like the single target did before
+            radius: 2000.0,
+            falloff: Falloff::Constant,
+        }
+    }
+}
+
+impl TargetSettings {
+    pub fn target(&self, position: Vec2) -> Target {
+        let angle = rand::thread_rng().gen_range(0.0..(PI * 2.0));
+        Target {
+            position,
+            velocity: Vec2::from_angle(angle) * self.speed,
+            behavior: self.behavior,
+            weight: self.weight,
+            radius: self.radius,
+            falloff: self.falloff,
+        }
+    }
+}
+
+/// The target being moved with the middle mouse button.
+#[derive(Debug, Default, Resource)]
+pub struct TargetDrag(pub Option<Entity>);
+
+fn find_target(
+    targets: &Query<(Entity, &mut Target), Without<RouteTarget>>,
+    cursor: Vec2,
+    distance: f32,
+) -> Option<Entity> {
+    targets
+        .iter()
+        .map(|(entity, target)| (entity, target.position.distance(cursor)))
+        .filter(|(_, target_distance)| *target_distance < distance)
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(entity, _)| entity)
+}
+
+/// Middle click places a target or grabs the one under the cursor, dragging moves it,
+/// right click deletes the target under the cursor. Clicks with shift edit the route,
+/// clicks with control edit paths and alt paints the flow field.
+#[allow(clippy::too_many_arguments)]
+pub fn update_targets_from_mouse(
+    mut commands: Commands,
+    buttons: Res<Input<MouseButton>>,
+    keys: Res<Input<KeyCode>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera_transform: Query<
+        (&Camera, &GlobalTransform, &OrthographicProjection),
+        With<MainCamera2d>,
+    >,
+    mut contexts: EguiContexts,
+    settings: Res<TargetSettings>,
+    mut drag: ResMut<TargetDrag>,
+    mut targets: Query<(Entity, &mut Target), Without<RouteTarget>>,
+) {
+    if buttons.just_released(MouseButton::Middle) {
+        drag.0 = None;
+    }
+    let (camera, camera_transform, projection) = camera_transform.single();
+    let window = windows.get_single().unwrap();
+    let Some(cursor) = get_cursor_world_position(window, camera, camera_transform) else {
+        return;
+    };
+    let pick_distance = PICK_DISTANCE * projection.scale;
+
+    if let Some(Ok((_, mut target))) = drag.0.map(|entity| targets.get_mut(entity)) {
+        if target.position != cursor {
+            target.position = cursor;
+        }
+    }
+
+    if contexts.ctx_mut().wants_pointer_input()
+        || keys.any_pressed([
+            KeyCode::ShiftLeft,
+            KeyCode::ShiftRight,
+            KeyCode::ControlLeft,
+            KeyCode::ControlRight,
+            KeyCode::AltLeft,
+            KeyCode::AltRight,
+        ])
+    {
+        return;
+    }
+    if buttons.just_pressed(MouseButton::Middle) {
+        drag.0 = Some(
+            find_target(&targets, cursor, pick_distance)
+                .unwrap_or_else(|| commands.spawn(settings.target(cursor)).id()),
+        );
+    } else if buttons.just_pressed(MouseButton::Right) {
+        if let Some(entity) = find_target(&targets, cursor, pick_distance) {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+/// Moves targets smoothly every frame by their velocity per tick, reflecting them off the
+/// walls.
+pub fn move_targets(time: Res<Time>, settings: Res<BoidSettings>, mut targets: Query<&mut Target>) {
+    let min = Vec2::new(settings.boundary_min_x, settings.boundary_min_y);
+    let max = Vec2::new(settings.boundary_max_x, settings.boundary_max_y);
+    let ticks = frame_ticks(&time, &settings);
+    for mut target in targets.iter_mut() {
+        if target.velocity == Vec2::ZERO {
+            continue;
+        }
+        let velocity = target.velocity;
+        target.position += velocity * ticks;
+        if (target.position.x < min.x && velocity.x < 0.0)
+            || (target.position.x > max.x && velocity.x > 0.0)
+        {
+            target.velocity.x = -velocity.x;
+        }
+        if (target.position.y < min.y && velocity.y < 0.0)
+            || (target.position.y > max.y && velocity.y > 0.0)
+        {
+            target.velocity.y = -velocity.y;
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::{Commands, Component, Entity, Local, Query, Res, Resource, Vec2, With};
+
+use crate::boids::{Boid, BoidTimer, Position};
+
+/// Recent positions of a boid, oldest first.
+#[derive(Debug, Default, Component)]
+pub struct Trail {
+    pub positions: VecDeque<Vec2>,
+}
+
+#[derive(Resource)]
+pub struct TrailSettings {
+    pub enabled: bool,
+    /// Number of positions kept per boid.
+    pub length: usize,
+    /// Number of simulation ticks between two samples.
+    pub sample_interval: u32,
+}
+
+impl Default for TrailSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            length: 40,
+            sample_interval: 5,
+        }
+    }
+}
+
+pub fn update_trails(
+    mut commands: Commands,
+    timer: Res<BoidTimer>,
+    trail_settings: Res<TrailSettings>,
+    mut ticks: Local<u32>,
+    mut boids: Query<(Entity, &Position, Option<&mut Trail>), With<Boid>>,
+) {
+    if !trail_settings.enabled {
+        for (_, _, trail) in boids.iter_mut() {
+            if let Some(mut trail) = trail.filter(|trail| !trail.positions.is_empty()) {
+                trail.positions.clear();
+            }
+        }
+        return;
+    }
+    if !timer.finished() {
+        return;
+    }
+    *ticks += 1;
+    if *ticks < trail_settings.sample_interval {
+        return;
+    }
+    *ticks = 0;
+
+    for (entity, position, trail) in boids.iter_mut() {
+        match trail {
+            Some(mut trail) => {
+                trail.positions.push_back(position.0);
+                while trail.positions.len() > trail_settings.length {
+                    trail.positions.pop_front();
+                }
+            }
+            None => {
+                commands.entity(entity).insert(Trail {
+                    positions: VecDeque::from([position.0]),
+                });
+            }
+        }
+    }
+}
@@ -1,13 +1,37 @@
-use bevy::{prelude::{ResMut}};
-use bevy_egui::{egui::{self, Vec2}, EguiContexts};
+use bevy::{prelude::{error, info, Commands, DetectChangesMut, Entity, Local, Query, Res, ResMut, With, Without}};
+use bevy_egui::{egui::{self, plot::{Line, Plot, PlotPoints}, Vec2}, EguiContexts};
 
-use crate::boids::BoidSettings;
+use crate::boids::{count_neighbors, Boid, BoidSettings, Position, SteeringForces, Velocity};
+use crate::boids3d::{Boids3dSettings, BoundsShape};
+use crate::camera::{CameraFollow, CameraSettings};
+use crate::clusters::Clusters;
+use crate::colormap::Colormap;
+use crate::debug::DebugOverlay;
+use crate::export::{ExportFormat, ExportSettings};
+use crate::flowfield::{FlowField, FlowFieldSettings};
+use crate::heatmap::Heatmap;
+use crate::leaders::{Leader, LeaderDrive, LeaderSettings};
+use crate::metrics::{FlockMetrics, MetricsHistory};
+use crate::params::{BoidParamSettings, BoidParams, Distribution};
+use crate::paths::{Path, PathEditing, PathShape};
+use crate::physics::PhysicsSettings;
+use crate::picking::SelectedBoid;
+use crate::raster::PngExport;
+use crate::render::{BoidColorMode, ColorLegend, RenderBackend, RenderSettings};
+use crate::routes::{Arrival, Route, RouteMode, RouteTarget};
+use crate::svg::SvgExport;
+use crate::targets::{Falloff, Target, TargetBehavior, TargetSettings};
+use crate::trails::TrailSettings;
+use crate::wind::Wind;
 
 
 pub fn update_ui(
     mut settings: ResMut<BoidSettings>,
+    settings_3d: Option<Res<Boids3dSettings>>,
     mut contexts: EguiContexts
 ) {
+    // the 3D flock only has flocking and seek, hide what it ignores
+    let flat = settings_3d.is_none();
     egui::Window::new("Boids Settings").show(contexts.ctx_mut(), |ui| {
         ui.style_mut().spacing.slider_width = 300.0;
 
@@ -19,7 +43,7 @@ pub fn update_ui(
         ui.add(egui::Slider::new(&mut settings.spawn_max_position, -600.0..=600.0).text("Max Spawn Position"));
         ui.add(egui::Slider::new(&mut settings.max_speed, 0.0..=2.0).text("Max Speed"));
         ui.add(egui::Slider::new(&mut settings.max_force, 0.0..=2.0).text("Max Force"));
-        ui.add(egui::Slider::new(&mut settings.velocity_time_scale, 0.0..=2.0).text("Velocity Time Scale"));
+        ui.add(egui::Slider::new(&mut settings.velocity_time_scale, 0.0..=30.0).text("Velocity Scale (px per tick)"));
 
         ui.add(egui::Slider::new(&mut settings.tick_time, 10..=150).text("Tick Time (ms)"));
 
@@ -33,11 +57,717 @@ pub fn update_ui(
         ui.add(egui::Slider::new(&mut settings.separation_weight, 0.0..=10.0).text("Separation Weight"));
 
         ui.add(egui::Slider::new(&mut settings.collision_weight, 0.0..=10.0).text("Collision Weight"));
+        if flat {
+            ui.add(egui::Slider::new(&mut settings.collision_iterations, 0..=10).text("Collision Iterations"));
+        }
 
         ui.add(egui::Slider::new(&mut settings.seek_weight, 0.0..=10.0).text("Target Seek Weight"));
+        if flat {
+            ui.add(egui::Slider::new(&mut settings.wander_weight, 0.0..=10.0).text("Wander Weight"));
+            ui.add(egui::Slider::new(&mut settings.wander_distance, 0.0..=150.0).text("Wander Circle Distance (px)"));
+            ui.add(egui::Slider::new(&mut settings.wander_radius, 0.0..=150.0).text("Wander Circle Radius (px)"));
+            ui.add(egui::Slider::new(&mut settings.wander_jitter, 0.0..=std::f32::consts::PI).text("Wander Jitter (rad per tick)"));
+
+            ui.add(egui::Slider::new(&mut settings.follow_weight, 0.0..=10.0).text("Leader Follow Weight"));
+            ui.add(egui::Slider::new(&mut settings.follow_distance, 0.0..=150.0).text("Leader Follow Distance (px)"));
+            ui.add(egui::Slider::new(&mut settings.leader_sight_radius, 0.0..=150.0).text("Leader Sight Radius (px)"));
+
+            ui.add(egui::Slider::new(&mut settings.path_weight, 0.0..=10.0).text("Path Weight"));
+            ui.add(egui::Slider::new(&mut settings.path_lookahead, 0.0..=150.0).text("Path Lookahead (px)"));
+
+            ui.add(egui::Slider::new(&mut settings.flow_weight, 0.0..=10.0).text("Flow Field Weight"));
+
+            ui.add(egui::Slider::new(&mut settings.arrival_radius, 5.0..=500.0).text("Target Arrival Radius (px)"));
+            ui.add(egui::Slider::new(&mut settings.max_prediction, 0.0..=500.0).text("Target Max Prediction (ticks)"));
+        }
 
         ui.set_min_size(Vec2::new(500.0, 500.0));
 
     });
 
-}
\ No newline at end of file
+}
+
+fn metrics_plot(ui: &mut egui::Ui, name: &str, history: &MetricsHistory, value: fn(&FlockMetrics) -> f32) {
+    let points: PlotPoints = history
+        .samples
+        .iter()
+        .map(|sample| [sample.tick as f64, value(sample) as f64])
+        .collect();
+    let current = history.samples.back().map(value).unwrap_or_default();
+    ui.label(format!("{}: {:.3}", name, current));
+    Plot::new(name)
+        .height(100.0)
+        .allow_drag(false)
+        .allow_zoom(false)
+        .allow_scroll(false)
+        .include_y(0.0)
+        .link_axis("metrics", true, false)
+        .show(ui, |plot_ui| plot_ui.line(Line::new(points).name(name)));
+}
+
+pub fn update_metrics_ui(
+    mut history: ResMut<MetricsHistory>,
+    clusters: Option<Res<Clusters>>,
+    mut contexts: EguiContexts
+) {
+    egui::Window::new("Flock Metrics").show(contexts.ctx_mut(), |ui| {
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut history.frozen, "Freeze");
+            if ui.button("Clear").clicked() {
+                history.clear();
+            }
+            ui.add(egui::Slider::new(&mut history.capacity, 100..=5000).text("Samples"));
+        });
+
+        metrics_plot(ui, "Polarization", &history, |sample| sample.polarization);
+        metrics_plot(ui, "Milling", &history, |sample| sample.milling);
+        metrics_plot(ui, "Density", &history, |sample| sample.density);
+        metrics_plot(ui, "Speed", &history, |sample| sample.speed);
+        metrics_plot(ui, "Contacts", &history, |sample| sample.contacts as f32);
+
+        // clusters are only detected in 2D
+        if let Some(clusters) = clusters {
+            let largest: Vec<String> = clusters.sizes.iter().take(8).map(|size| size.to_string()).collect();
+            ui.label(format!("Clusters: {} (largest: {})", clusters.count, largest.join(", ")));
+        }
+
+        ui.set_min_width(400.0);
+    });
+}
+
+pub fn update_export_ui(
+    mut export: ResMut<ExportSettings>,
+    mut svg_export: ResMut<SvgExport>,
+    mut png_export: ResMut<PngExport>,
+    mut path_drafts: Local<Option<(String, String)>>,
+    mut contexts: EguiContexts
+) {
+    egui::Window::new("Export").default_open(false).show(contexts.ctx_mut(), |ui| {
+        // only flag the settings as changed on edits, a change reopens the export files
+        let settings = export.bypass_change_detection();
+        let mut changed = false;
+        // paths are edited in drafts and applied as a whole, not on every keystroke
+        let (metrics_path, boids_path) = path_drafts.get_or_insert_with(|| (settings.metrics_path.clone(), settings.boids_path.clone()));
+        let mut apply_paths = false;
+
+        changed |= ui.checkbox(&mut settings.enabled, "Export every tick").changed();
+        ui.horizontal(|ui| {
+            changed |= ui.radio_value(&mut settings.format, ExportFormat::Csv, "CSV").changed();
+            changed |= ui.radio_value(&mut settings.format, ExportFormat::Json, "JSON lines").changed();
+        });
+        ui.horizontal(|ui| {
+            ui.label("Metrics");
+            apply_paths |= ui.text_edit_singleline(metrics_path).lost_focus();
+        });
+        changed |= ui.checkbox(&mut settings.include_boids, "Include boid trajectories").changed();
+        ui.horizontal(|ui| {
+            ui.label("Boids");
+            apply_paths |= ui.text_edit_singleline(boids_path).lost_focus();
+        });
+        apply_paths |= ui.button("Apply Paths").clicked();
+        if apply_paths && (settings.metrics_path != *metrics_path || settings.boids_path != *boids_path) {
+            settings.metrics_path = metrics_path.clone();
+            settings.boids_path = boids_path.clone();
+            changed = true;
+        }
+
+        if changed {
+            export.set_changed();
+        }
+
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.label("SVG");
+            ui.text_edit_singleline(&mut svg_export.path);
+        });
+        ui.add(egui::Slider::new(&mut svg_export.every, 0..=1000).text("SVG Every N Ticks (0 = off)"));
+        if ui.button("Save SVG").clicked() {
+            svg_export.requested = true;
+        }
+
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.label("PNG");
+            ui.text_edit_singleline(&mut png_export.path);
+        });
+        ui.add(egui::Slider::new(&mut png_export.width, 256..=4096).text("PNG Width (px)"));
+        ui.add(egui::Slider::new(&mut png_export.every, 0..=1000).text("PNG Every N Ticks (0 = off)"));
+        if ui.button("Save PNG").clicked() {
+            png_export.requested = true;
+        }
+    });
+}
+
+fn to_color32(color: bevy::prelude::Color) -> egui::Color32 {
+    let [r, g, b, a] = color.as_rgba_u8();
+    egui::Color32::from_rgba_unmultiplied(r, g, b, a)
+}
+
+fn color_legend(ui: &mut egui::Ui, render_settings: &RenderSettings, legend: &ColorLegend) {
+    let colormap = render_settings.colormap;
+    if render_settings.color_mode.is_categorical() {
+        let palette_size = colormap.category_count();
+        let wraps = legend.max as usize >= palette_size;
+        ui.horizontal_wrapped(|ui| {
+            for id in 0..(legend.max as usize + 1).min(palette_size) {
+                let (rect, _) = ui.allocate_exact_size(Vec2::splat(12.0), egui::Sense::hover());
+                ui.painter().rect_filled(rect, 2.0, to_color32(colormap.category(id)));
+                ui.label(if wraps { format!("{} mod {}", id, palette_size) } else { id.to_string() });
+            }
+        });
+        if wraps {
+            ui.label(format!("Ids up to {} share the {} colors, id mod {}", legend.max, palette_size, palette_size));
+        }
+        return;
+    }
+
+    let steps = 64;
+    let (rect, _) = ui.allocate_exact_size(Vec2::new(300.0, 16.0), egui::Sense::hover());
+    let step_width = rect.width() / steps as f32;
+    for step in 0..steps {
+        let left = rect.left() + step as f32 * step_width;
+        let step_rect = egui::Rect::from_min_max(
+            egui::pos2(left, rect.top()),
+            egui::pos2(left + step_width + 0.5, rect.bottom()),
+        );
+        let color = colormap.sample(step as f32 / (steps - 1) as f32);
+        ui.painter().rect_filled(step_rect, 0.0, to_color32(color));
+    }
+    ui.horizontal(|ui| {
+        ui.label(format!("{:.3}", legend.min));
+        ui.add_space(240.0);
+        ui.label(format!("{:.3}", legend.max));
+    });
+}
+
+pub fn update_render_ui(
+    mut render_settings: ResMut<RenderSettings>,
+    mut heatmap: ResMut<Heatmap>,
+    mut overlay: ResMut<DebugOverlay>,
+    mut camera_settings: ResMut<CameraSettings>,
+    mut trail_settings: ResMut<TrailSettings>,
+    legend: Res<ColorLegend>,
+    mut contexts: EguiContexts
+) {
+    egui::Window::new("Render Settings").default_open(false).show(contexts.ctx_mut(), |ui| {
+        ui.horizontal(|ui| {
+            ui.label("Backend");
+            ui.radio_value(&mut render_settings.backend, RenderBackend::Lyon, "Lyon Shapes");
+            ui.radio_value(&mut render_settings.backend, RenderBackend::Batched, "Batched Mesh");
+        });
+        ui.checkbox(&mut render_settings.interpolate, "Interpolate Heading Between Ticks");
+
+        egui::ComboBox::from_label("Boid Color")
+            .selected_text(render_settings.color_mode.name())
+            .show_ui(ui, |ui| {
+                for mode in BoidColorMode::ALL {
+                    ui.selectable_value(&mut render_settings.color_mode, mode, mode.name());
+                }
+            });
+        if render_settings.color_mode != BoidColorMode::Uniform {
+            egui::ComboBox::from_label("Colormap")
+                .selected_text(render_settings.colormap.name())
+                .show_ui(ui, |ui| {
+                    for colormap in Colormap::ALL {
+                        ui.selectable_value(&mut render_settings.colormap, colormap, colormap.name());
+                    }
+                });
+            color_legend(ui, &render_settings, &legend);
+        }
+
+        ui.separator();
+        ui.checkbox(&mut render_settings.show_heatmap, "Show Density Heatmap");
+        // the heatmap is only written to when touched, so the overlay is not redrawn every frame
+        let mut decay = heatmap.decay;
+        if ui.add(egui::Slider::new(&mut decay, 0.9..=1.0).text("Heatmap Decay")).changed() {
+            heatmap.decay = decay;
+        }
+        if ui.button("Reset Heatmap").clicked() {
+            heatmap.reset();
+        }
+
+        ui.separator();
+        ui.checkbox(&mut trail_settings.enabled, "Show Trails");
+        ui.add(egui::Slider::new(&mut trail_settings.length, 2..=500).text("Trail Length (samples)"));
+        ui.add(egui::Slider::new(&mut trail_settings.sample_interval, 1..=50).text("Trail Sample Interval (ticks)"));
+
+        ui.separator();
+        ui.checkbox(&mut overlay.enabled, "Show Debug Overlay");
+        ui.add(egui::Slider::new(&mut overlay.force_scale, 1.0..=100000.0).logarithmic(true).text("Force Scale (px)"));
+        ui.label("Radii and neighbors: separation red, alignment green, cohesion blue");
+        ui.label("Forces: collision orange, seek purple, wander yellow, follow pink, path teal,");
+        ui.label("flow olive, boundary cyan, wind white, drag gray");
+
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.label("Camera Follow");
+            ui.radio_value(&mut camera_settings.follow, CameraFollow::Off, "Off");
+            ui.radio_value(&mut camera_settings.follow, CameraFollow::Centroid, "Flock Centroid");
+            ui.radio_value(&mut camera_settings.follow, CameraFollow::SelectedBoid, "Selected Boid");
+        });
+        if ui.button("Fit Arena (F)").clicked() {
+            camera_settings.fit_arena = true;
+        }
+        ui.label("Drag with the left mouse button to pan, scroll to zoom");
+    });
+}
+
+fn vector_label(ui: &mut egui::Ui, name: &str, vector: bevy::prelude::Vec2) {
+    ui.label(name);
+    ui.label(format!("({:.4}, {:.4})", vector.x, vector.y));
+    ui.label(format!("{:.4}", vector.length()));
+    ui.end_row();
+}
+
+#[allow(clippy::type_complexity)]
+pub fn update_inspector_ui(
+    mut commands: Commands,
+    mut selected: ResMut<SelectedBoid>,
+    mut camera_settings: ResMut<CameraSettings>,
+    settings: Res<BoidSettings>,
+    physics: Res<PhysicsSettings>,
+    boids: Query<(Entity, &Position, &Velocity, &SteeringForces, Option<&Leader>, Option<&BoidParams>), With<Boid>>,
+    mut contexts: EguiContexts
+) {
+    let Some(Ok((entity, position, velocity, forces, leader, params))) = selected.0.map(|entity| boids.get(entity)) else {
+        return;
+    };
+    let all: Vec<bevy::prelude::Vec2> = boids
+        .iter()
+        .map(|(_, position, _, _, _, _)| position.0)
+        .collect();
+    let params = params.copied().unwrap_or_else(|| BoidParams::from_settings(&settings, &physics));
+
+    let mut open = true;
+    egui::Window::new("Boid Inspector").open(&mut open).show(contexts.ctx_mut(), |ui| {
+        ui.label(format!("Entity: {:?}", entity));
+        ui.label(format!("Position: ({:.1}, {:.1})", position.0.x, position.0.y));
+        ui.label(format!("Velocity: ({:.4}, {:.4})", velocity.0.x, velocity.0.y));
+        ui.label(format!("Speed: {:.4} of {:.4}", velocity.0.length(), params.max_speed));
+        ui.label(format!("Max Force: {:.4}", params.max_force));
+        if physics.enabled {
+            ui.label(format!("Mass: {:.2}", params.mass));
+        }
+
+        let mut follow = camera_settings.follow == CameraFollow::SelectedBoid;
+        if ui.checkbox(&mut follow, "Follow with camera").changed() {
+            camera_settings.follow = if follow { CameraFollow::SelectedBoid } else { CameraFollow::Off };
+        }
+        let mut is_leader = leader.is_some();
+        if ui.checkbox(&mut is_leader, "Leader").changed() {
+            if is_leader {
+                commands.entity(entity).insert(Leader);
+            } else {
+                commands.entity(entity).remove::<Leader>();
+            }
+        }
+
+        ui.separator();
+        egui::Grid::new("inspector_neighbors").show(ui, |ui| {
+            ui.label("Neighbors");
+            ui.label("Radius");
+            ui.label("Count");
+            ui.end_row();
+            for (name, radius) in [
+                ("Separation", params.separation_radius),
+                ("Alignment", params.alignment_radius),
+                ("Cohesion", params.cohesion_radius),
+                ("Collision", settings.boid_radius),
+            ] {
+                ui.label(name);
+                ui.label(format!("{:.1}", radius));
+                ui.label(count_neighbors(position.0, all.iter().copied(), radius).to_string());
+                ui.end_row();
+            }
+        });
+
+        ui.separator();
+        egui::Grid::new("inspector_forces").show(ui, |ui| {
+            ui.label("Force");
+            ui.label("Vector");
+            ui.label("Magnitude");
+            ui.end_row();
+            vector_label(ui, "Separation", forces.separation);
+            vector_label(ui, "Alignment", forces.alignment);
+            vector_label(ui, "Cohesion", forces.cohesion);
+            vector_label(ui, "Collision", forces.collision);
+            vector_label(ui, "Seek", forces.seek);
+            vector_label(ui, "Wander", forces.wander);
+            vector_label(ui, "Follow", forces.follow);
+            vector_label(ui, "Path", forces.path);
+            vector_label(ui, "Flow", forces.flow);
+            vector_label(ui, "Boundary", forces.boundary);
+            vector_label(ui, "Wind", forces.wind);
+            vector_label(ui, "Drag", forces.drag);
+        });
+    });
+    if !open {
+        selected.0 = None;
+    }
+}
+
+/// Behavior, weight, radius and falloff controls, returns whether any of them changed.
+fn target_controls(ui: &mut egui::Ui, id: impl std::hash::Hash + Copy, behavior: &mut TargetBehavior, weight: &mut f32, radius: &mut f32, falloff: &mut Falloff) -> bool {
+    let mut changed = false;
+    egui::ComboBox::from_id_source((id, "behavior"))
+        .selected_text(behavior.name())
+        .show_ui(ui, |ui| {
+            for option in TargetBehavior::ALL {
+                changed |= ui.selectable_value(behavior, option, option.name()).changed();
+            }
+        });
+    changed |= ui.add(egui::Slider::new(weight, -5.0..=5.0).text("Weight")).changed();
+    changed |= ui.add(egui::Slider::new(radius, 10.0..=2000.0).logarithmic(true).text("Radius (px)")).changed();
+    egui::ComboBox::from_id_source((id, "falloff"))
+        .selected_text(falloff.name())
+        .show_ui(ui, |ui| {
+            for option in Falloff::ALL {
+                changed |= ui.selectable_value(falloff, option, option.name()).changed();
+            }
+        });
+    changed
+}
+
+pub fn update_targets_ui(
+    mut commands: Commands,
+    mut target_settings: ResMut<TargetSettings>,
+    mut targets: Query<(Entity, &mut Target), Without<RouteTarget>>,
+    mut contexts: EguiContexts
+) {
+    egui::Window::new("Targets").default_open(false).show(contexts.ctx_mut(), |ui| {
+        ui.label("Middle click to place or drag a target, right click to delete it");
+        ui.label("Negative weights repel boids");
+
+        ui.separator();
+        ui.label("New Targets");
+        let TargetSettings { behavior, speed, weight, radius, falloff } = &mut *target_settings;
+        target_controls(ui, "new_target", behavior, weight, radius, falloff);
+        ui.add(egui::Slider::new(speed, 0.0..=10.0).text("Speed (px per tick)"));
+
+        ui.separator();
+        if ui.button("Delete All").clicked() {
+            for (entity, _) in targets.iter() {
+                commands.entity(entity).despawn();
+            }
+        }
+        // only write targets that were edited, so their renderables are not updated every frame
+        for (entity, mut target) in targets.iter_mut() {
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.label(format!("{:?} at ({:.0}, {:.0}), speed {:.2}", entity, target.position.x, target.position.y, target.velocity.length()));
+                if ui.button("Delete").clicked() {
+                    commands.entity(entity).despawn();
+                }
+            });
+            let Target { mut behavior, mut weight, mut radius, mut falloff, .. } = *target;
+            if target_controls(ui, entity, &mut behavior, &mut weight, &mut radius, &mut falloff) {
+                target.behavior = behavior;
+                target.weight = weight;
+                target.radius = radius;
+                target.falloff = falloff;
+            }
+        }
+    });
+}
+
+pub fn update_route_ui(
+    mut route: ResMut<Route>,
+    mut contexts: EguiContexts
+) {
+    egui::Window::new("Route").default_open(false).show(contexts.ctx_mut(), |ui| {
+        ui.label("Shift and middle click to add a waypoint, shift and right click to delete it");
+
+        ui.horizontal(|ui| {
+            ui.label("Mode");
+            for mode in RouteMode::ALL {
+                ui.radio_value(&mut route.mode, mode, mode.name());
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Arrival");
+            ui.radio_value(&mut route.arrival, Arrival::Centroid, "Flock Centroid");
+            ui.radio_value(&mut route.arrival, Arrival::Fraction, "Fraction of Boids");
+        });
+        ui.add(egui::Slider::new(&mut route.arrival_radius, 10.0..=500.0).text("Arrival Radius (px)"));
+        if route.arrival == Arrival::Fraction {
+            ui.add(egui::Slider::new(&mut route.arrival_fraction, 0.0..=1.0).text("Arrival Fraction"));
+        }
+        ui.add(egui::Slider::new(&mut route.weight, -5.0..=5.0).text("Weight"));
+
+        ui.separator();
+        match route.current {
+            Some(current) => ui.label(format!("Heading to waypoint {} of {}, {} reached", current + 1, route.waypoints.len(), route.reached)),
+            None => ui.label(format!("Stopped, {} reached", route.reached)),
+        };
+        ui.horizontal(|ui| {
+            if ui.button("Start").clicked() {
+                route.start();
+            }
+            if ui.button("Stop").clicked() {
+                route.stop();
+            }
+            if ui.button("Clear").clicked() {
+                route.waypoints.clear();
+                route.stop();
+            }
+        });
+
+        let mut removed = None;
+        egui::Grid::new("route_waypoints").show(ui, |ui| {
+            for (index, waypoint) in route.waypoints.iter().enumerate() {
+                ui.label(format!("{}", index + 1));
+                ui.label(format!("({:.0}, {:.0})", waypoint.x, waypoint.y));
+                if ui.button("Delete").clicked() {
+                    removed = Some(index);
+                }
+                ui.end_row();
+            }
+        });
+        if let Some(index) = removed {
+            route.remove(index);
+        }
+    });
+}
+
+pub fn update_leaders_ui(
+    mut leader_settings: ResMut<LeaderSettings>,
+    leaders: Query<(), (With<Boid>, With<Leader>)>,
+    mut contexts: EguiContexts
+) {
+    egui::Window::new("Leaders").default_open(false).show(contexts.ctx_mut(), |ui| {
+        ui.label(format!("Leaders: {}", leaders.iter().count()));
+        ui.label("Toggle single leaders in the boid inspector");
+        ui.add(egui::Slider::new(&mut leader_settings.count, 0..=20).text("Leaders on Respawn"));
+
+        ui.horizontal(|ui| {
+            ui.label("Drive");
+            for drive in LeaderDrive::ALL {
+                ui.radio_value(&mut leader_settings.drive, drive, drive.name());
+            }
+        });
+        if leader_settings.drive != LeaderDrive::Targets {
+            ui.add(egui::Slider::new(&mut leader_settings.weight, 0.0..=10.0).text("Drive Weight"));
+        }
+        ui.checkbox(&mut leader_settings.followers_seek_targets, "Followers Seek Targets");
+    });
+}
+
+pub fn update_paths_ui(
+    mut commands: Commands,
+    mut editing: ResMut<PathEditing>,
+    mut paths: Query<(Entity, &mut Path)>,
+    mut contexts: EguiContexts
+) {
+    egui::Window::new("Paths").default_open(false).show(contexts.ctx_mut(), |ui| {
+        ui.label("Control and middle click to add a point to the edited path or drag a point,");
+        ui.label("control and right click to delete a point");
+        if ui.button("New Path").clicked() {
+            editing.active = None;
+        }
+
+        // only write paths that were edited, the boids resample them every tick anyway
+        for (entity, mut path) in paths.iter_mut() {
+            ui.separator();
+            ui.horizontal(|ui| {
+                let mut active = editing.active == Some(entity);
+                if ui.checkbox(&mut active, format!("{:?}, {} points", entity, path.control_points.len())).changed() {
+                    editing.active = active.then_some(entity);
+                }
+                if ui.button("Delete").clicked() {
+                    commands.entity(entity).despawn();
+                }
+            });
+            let mut shape = path.shape;
+            egui::ComboBox::from_id_source((entity, "shape"))
+                .selected_text(shape.name())
+                .show_ui(ui, |ui| {
+                    for option in PathShape::ALL {
+                        ui.selectable_value(&mut shape, option, option.name());
+                    }
+                });
+            let mut width = path.width;
+            ui.add(egui::Slider::new(&mut width, 2.0..=300.0).text("Width (px)"));
+            let mut closed = path.closed;
+            ui.checkbox(&mut closed, "Closed");
+            if (shape, width, closed) != (path.shape, path.width, path.closed) {
+                path.shape = shape;
+                path.width = width;
+                path.closed = closed;
+            }
+        }
+    });
+}
+
+pub fn update_flow_field_ui(
+    mut field: ResMut<FlowField>,
+    mut field_settings: ResMut<FlowFieldSettings>,
+    mut render_settings: ResMut<RenderSettings>,
+    mut contexts: EguiContexts
+) {
+    egui::Window::new("Flow Field").default_open(false).show(contexts.ctx_mut(), |ui| {
+        ui.checkbox(&mut render_settings.show_flow_field, "Show Flow Field");
+        ui.label("Alt and middle drag to paint the field");
+        ui.add(egui::Slider::new(&mut field_settings.brush_radius, 10.0..=500.0).text("Brush Radius (px)"));
+
+        ui.separator();
+        ui.add(egui::Slider::new(&mut field_settings.noise_seed, 0..=1000).text("Noise Seed"));
+        ui.add(egui::Slider::new(&mut field_settings.noise_scale, 50.0..=2000.0).logarithmic(true).text("Noise Scale (px)"));
+        ui.add(egui::Slider::new(&mut field_settings.vortex_pull, -1.0..=1.0).text("Vortex Pull"));
+        ui.horizontal(|ui| {
+            if ui.button("Curl Noise").clicked() {
+                field.fill_curl_noise(field_settings.noise_seed, field_settings.noise_scale);
+            }
+            if ui.button("Vortex").clicked() {
+                let center = field.min + bevy::prelude::Vec2::new(field.width as f32, field.height as f32) * field.cell_size / 2.0;
+                field.fill_vortex(center, field_settings.vortex_pull);
+            }
+            if ui.button("Clear").clicked() {
+                field.clear();
+            }
+        });
+
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.label("File");
+            ui.text_edit_singleline(&mut field_settings.path);
+        });
+        ui.horizontal(|ui| {
+            if ui.button("Load").clicked() {
+                match FlowField::load(&field_settings.path) {
+                    Ok(loaded) => *field = loaded,
+                    Err(err) => error!("{}", err),
+                }
+            }
+            if ui.button("Save").clicked() {
+                match field.save(&field_settings.path) {
+                    Ok(()) => info!("wrote {}", field_settings.path),
+                    Err(err) => error!("{}", err),
+                }
+            }
+        });
+    });
+}
+
+pub fn update_wind_ui(
+    mut wind: ResMut<Wind>,
+    mut contexts: EguiContexts
+) {
+    egui::Window::new("Wind").default_open(false).show(contexts.ctx_mut(), |ui| {
+        ui.add(egui::Slider::new(&mut wind.direction, -std::f32::consts::PI..=std::f32::consts::PI).text("Direction (rad)"));
+        ui.add(egui::Slider::new(&mut wind.strength, 0.0..=0.1).text("Strength"));
+
+        ui.separator();
+        ui.add(egui::Slider::new(&mut wind.gust_strength, 0.0..=5.0).text("Gust Strength"));
+        ui.add(egui::Slider::new(&mut wind.gust_period, 10.0..=1000.0).logarithmic(true).text("Gust Period (ticks)"));
+        ui.label(format!("Current Gust: {:.2}x", wind.gust));
+
+        ui.separator();
+        ui.add(egui::Slider::new(&mut wind.turbulence_strength, 0.0..=0.1).text("Turbulence Strength"));
+        ui.add(egui::Slider::new(&mut wind.turbulence_scale, 20.0..=2000.0).logarithmic(true).text("Turbulence Scale (px)"));
+        ui.add(egui::Slider::new(&mut wind.turbulence_speed, 0.0..=0.1).text("Turbulence Drift (per tick)"));
+        ui.add(egui::Slider::new(&mut wind.seed, 0..=1000).text("Noise Seed"));
+    });
+}
+
+/// Shape selection and the sliders of its parameters, within the given range.
+fn distribution_controls(ui: &mut egui::Ui, name: &str, distribution: &mut Distribution, range: std::ops::RangeInclusive<f32>) {
+    ui.horizontal(|ui| {
+        ui.label(name);
+        egui::ComboBox::from_id_source(name)
+            .selected_text(distribution.name())
+            .show_ui(ui, |ui| {
+                for shape in Distribution::SHAPES {
+                    if ui.selectable_label(distribution.name() == shape, shape).clicked() {
+                        *distribution = distribution.with_shape(shape);
+                    }
+                }
+            });
+    });
+    let spread = *range.end() - *range.start();
+    match distribution {
+        Distribution::Constant(value) => {
+            ui.add(egui::Slider::new(value, range).text("Value"));
+        }
+        Distribution::Uniform { min, max } => {
+            ui.add(egui::Slider::new(min, range.clone()).text("Min"));
+            ui.add(egui::Slider::new(max, range).text("Max"));
+            *max = max.max(*min);
+        }
+        Distribution::Normal { mean, std_dev } => {
+            ui.add(egui::Slider::new(mean, range).text("Mean"));
+            ui.add(egui::Slider::new(std_dev, 0.0..=spread / 2.0).text("Standard Deviation"));
+        }
+    }
+}
+
+pub fn update_params_ui(
+    mut param_settings: ResMut<BoidParamSettings>,
+    settings: Res<BoidSettings>,
+    physics: Res<PhysicsSettings>,
+    params: Query<&BoidParams, With<Boid>>,
+    mut contexts: EguiContexts
+) {
+    egui::Window::new("Boid Parameters").default_open(false).show(contexts.ctx_mut(), |ui| {
+        if ui.checkbox(&mut param_settings.enabled, "Per Boid Parameters").changed() && param_settings.enabled {
+            param_settings.seed_constants(&settings, &physics);
+        }
+        ui.label("Drawn when spawning, press space to respawn the boids");
+        ui.label(format!("Boids with own parameters: {}", params.iter().count()));
+
+        let param_settings = &mut *param_settings;
+        ui.add_enabled_ui(param_settings.enabled, |ui| {
+            ui.separator();
+            distribution_controls(ui, "Max Speed", &mut param_settings.max_speed, 0.0..=2.0);
+            ui.separator();
+            distribution_controls(ui, "Max Force", &mut param_settings.max_force, 0.0..=2.0);
+            ui.separator();
+            distribution_controls(ui, "Separation Radius", &mut param_settings.separation_radius, 0.0..=150.0);
+            ui.separator();
+            distribution_controls(ui, "Alignment Radius", &mut param_settings.alignment_radius, 0.0..=150.0);
+            ui.separator();
+            distribution_controls(ui, "Cohesion Radius", &mut param_settings.cohesion_radius, 0.0..=150.0);
+            ui.separator();
+            distribution_controls(ui, "Mass", &mut param_settings.mass, 0.0..=10.0);
+        });
+    });
+}
+
+pub fn update_physics_ui(
+    mut physics: ResMut<PhysicsSettings>,
+    settings: Res<BoidSettings>,
+    mut contexts: EguiContexts
+) {
+    egui::Window::new("Physics").default_open(false).show(contexts.ctx_mut(), |ui| {
+        ui.checkbox(&mut physics.enabled, "Mass, Drag and Stall Speed");
+        let physics = &mut *physics;
+        ui.add_enabled_ui(physics.enabled, |ui| {
+            ui.add(egui::Slider::new(&mut physics.mass, 0.1..=10.0).logarithmic(true).text("Mass"));
+            ui.add(egui::Slider::new(&mut physics.linear_drag, 0.0..=0.5).text("Linear Drag"));
+            ui.add(egui::Slider::new(&mut physics.quadratic_drag, 0.0..=5.0).text("Quadratic Drag"));
+            ui.add(egui::Slider::new(&mut physics.min_speed, 0.0..=settings.max_speed).text("Min Speed (up to Max Speed)"));
+            ui.label("Per boid masses are drawn in the boid parameters window");
+        });
+    });
+}
+
+pub fn update_3d_ui(
+    mut settings_3d: ResMut<Boids3dSettings>,
+    mut contexts: EguiContexts
+) {
+    egui::Window::new("3D").show(contexts.ctx_mut(), |ui| {
+        ui.horizontal(|ui| {
+            ui.label("Bounds");
+            for shape in BoundsShape::ALL {
+                ui.radio_value(&mut settings_3d.bounds, shape, shape.name());
+            }
+        });
+        ui.add(egui::Slider::new(&mut settings_3d.bounds_size, 50.0..=1000.0).text("Bounds Size (px)"));
+        ui.horizontal(|ui| {
+            ui.label("Seek Target");
+            ui.add(egui::DragValue::new(&mut settings_3d.seek_target.x).prefix("x: "));
+            ui.add(egui::DragValue::new(&mut settings_3d.seek_target.y).prefix("y: "));
+            ui.add(egui::DragValue::new(&mut settings_3d.seek_target.z).prefix("z: "));
+        });
+        ui.label("Drag with the left mouse button to orbit, scroll to zoom");
+    });
+}
@@ -0,0 +1,73 @@
+use bevy::prelude::{Res, ResMut, Resource, Vec2};
+
+use crate::boids::BoidTimer;
+use crate::noise::{curl_noise, value_noise};
+
+/// External force added to the acceleration of every boid, on top of the limited
+/// steering force.
+#[derive(Debug, Resource)]
+pub struct Wind {
+    /// Direction the wind blows towards, in radians.
+    pub direction: f32,
+    pub strength: f32,
+    /// How much stronger than the base strength gusts get, 1.0 doubles the wind.
+    pub gust_strength: f32,
+    /// Average number of ticks between gusts.
+    pub gust_period: f32,
+    /// Strength of the spatially varying turbulence, independent of the wind direction.
+    pub turbulence_strength: f32,
+    /// Size of a turbulence swirl in pixels.
+    pub turbulence_scale: f32,
+    /// How far the turbulence drifts per tick, in swirls.
+    pub turbulence_speed: f32,
+    pub seed: u32,
+    /// Wind multiplier of the current tick, 1.0 between gusts.
+    pub gust: f32,
+    ticks: u64,
+}
+
+impl Default for Wind {
+    fn default() -> Self {
+        Self {
+            direction: 0.0,
+            strength: 0.0,
+            gust_strength: 0.0,
+            gust_period: 200.0,
+            turbulence_strength: 0.0,
+            turbulence_scale: 200.0,
+            turbulence_speed: 0.01,
+            seed: 0,
+            gust: 1.0,
+            ticks: 0,
+        }
+    }
+}
+
+impl Wind {
+    /// Force the wind applies to a boid at the given position during the current tick.
+    pub fn force(&self, position: Vec2) -> Vec2 {
+        let mut force = Vec2::from_angle(self.direction) * self.strength * self.gust;
+        if self.turbulence_strength > 0.0 {
+            let drift = Vec2::splat(self.ticks as f32 * self.turbulence_speed);
+            // the curl of the value noise rarely gets longer than 2.0
+            let turbulence = curl_noise(self.seed, position / self.turbulence_scale + drift) / 2.0;
+            force += turbulence.clamp_length_max(1.0) * self.turbulence_strength;
+        }
+        force
+    }
+}
+
+/// Advances gusts and turbulence once per simulation tick.
+pub fn update_wind(timer: Res<BoidTimer>, mut wind: ResMut<Wind>) {
+    if !timer.finished() {
+        return;
+    }
+    wind.ticks += 1;
+    wind.gust = if wind.gust_strength > 0.0 && wind.gust_period > 0.0 {
+        // only the upper half of the noise blows, so gusts come with calm spells in between
+        let time = Vec2::new(wind.ticks as f32 / wind.gust_period, 0.5);
+        1.0 + wind.gust_strength * value_noise(wind.seed.wrapping_add(1), time).max(0.0)
+    } else {
+        1.0
+    };
+}